@@ -0,0 +1,854 @@
+#![no_std]
+
+//! Contrato de compra colectiva (pools) en Soroban.
+//!
+//! Un creador abre un pool con una meta en un token SAC y una fecha límite.
+//! Los miembros aportan con `contribute` (previo `approve` al contrato); si se
+//! alcanza la meta el creador llama `finalize` y todo lo recaudado se envía al
+//! proveedor. Si vence sin llegar a la meta, cada miembro recupera su aporte
+//! con `refund`.
+//!
+//! Cada pool puede fijar límites (`PoolLimits`): aporte mínimo, tope por
+//! miembro y cantidad máxima de miembros; `contribute` los hace cumplir.
+//!
+//! Registros de socios: una cooperativa crea un registro con `create_registry`
+//! y su administrador agrega o quita direcciones. Un pool que referencia un
+//! registro solo acepta aportes de sus socios.
+//!
+//! Un pool puede tener hitos de entrega: en vez de `finalize`, el creador
+//! confirma cada entrega con `release_milestone` y se libera el porcentaje de
+//! ese hito. Si vence el plazo del siguiente hito sin confirmarse, lo que no se
+//! liberó queda reembolsable a prorrata.
+//!
+//! El creador puede cancelar un pool no finalizado con `cancel_pool` (p. ej. si
+//! el proveedor retira la oferta): desde ese momento cada miembro recupera con
+//! `refund` su aporte, o su parte de lo no liberado si ya hubo entregas.
+//!
+//! Extensión de plazo: antes del vencimiento, un aportante (o el creador)
+//! propone una nueva fecha con `propose_extension` y los aportantes la votan con
+//! `vote_extension`, cada uno con el peso de su aporte. Cuando los votos llegan a
+//! la mayoría del pool (`extension_quorum_bps` de lo recaudado) el plazo se mueve.
+//!
+//! Eventos emitidos (el indexador de `server.js` depende de estos tags):
+//! - `("pc", id)` → `Pool` recién creado
+//! - `("ctr", id, contributor)` → monto aportado (`i128`)
+//! - `("ms", id, index)` → monto liberado al proveedor por el hito (`i128`)
+//! - `("fn", id)` → monto enviado al proveedor (`i128`; con hitos, el total liberado)
+//! - `("rf", id, user)` → monto reembolsado (`i128`)
+//! - `("cn", id)` → pool cancelado; el dato es lo que queda por reembolsar (`i128`,
+//!   sin lo ya liberado ni lo ya reembolsado)
+//! - `("xp", id)` → nueva fecha propuesta para el plazo (`u64`)
+//! - `("xv", id, voter)` → peso del voto a favor (`i128`, su aporte)
+//! - `("xt", id)` → plazo extendido; el dato es la nueva fecha (`u64`)
+//! - `("rg", registry_id)` → administrador del registro creado (`Address`)
+//! - `("ma", registry_id, member)` / `("mr", registry_id, member)` → socio
+//!   agregado / quitado; el dato es la cantidad de socios resultante (`u32`)
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Vec,
+};
+
+// TTL de almacenamiento (en ledgers, ~5 s cada uno)
+const DAY_IN_LEDGERS: u32 = 17_280;
+const INSTANCE_BUMP: u32 = 7 * DAY_IN_LEDGERS;
+const INSTANCE_THRESHOLD: u32 = INSTANCE_BUMP - DAY_IN_LEDGERS;
+const POOL_BUMP: u32 = 30 * DAY_IN_LEDGERS;
+const POOL_THRESHOLD: u32 = POOL_BUMP - DAY_IN_LEDGERS;
+// Los porcentajes de los hitos van en puntos base y deben sumar 100 %
+const BPS_TOTAL: u32 = 10_000;
+const MAX_MILESTONES: u32 = 10;
+// Mayoría por defecto para extender el plazo (más de la mitad de lo recaudado)
+const DEFAULT_EXTENSION_QUORUM_BPS: u32 = 5_001;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    PoolNotFound = 3,
+    InvalidAmount = 4,
+    InvalidDeadline = 5,
+    PoolExpired = 6,
+    AlreadyFinalized = 7,
+    GoalNotReached = 8,
+    NotCreator = 9,
+    RefundNotAvailable = 10,
+    NothingToRefund = 11,
+    GoalExceeded = 12,
+    InvalidMilestones = 13,
+    HasMilestones = 14,
+    NoMilestonePending = 15,
+    MilestoneLapsed = 16,
+    InvalidLimits = 17,
+    BelowMinContribution = 18,
+    MemberCapExceeded = 19,
+    MaxMembersReached = 20,
+    RegistryNotFound = 21,
+    NotRegistryAdmin = 22,
+    NotMember = 23,
+    AlreadyMember = 24,
+    PoolCancelled = 25,
+    InvalidExtension = 26,
+    NoExtensionPending = 27,
+    ExtensionPending = 28,
+    AlreadyVoted = 29,
+    NotContributor = 30,
+}
+
+/// Límites de aporte de un pool; 0 significa "sin límite".
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolLimits {
+    pub min_contribution: i128,
+    pub max_contribution_per_member: i128,
+    pub max_members: u32,
+}
+
+/// Términos opcionales de `create_pool`: hitos de entrega, límites de aporte,
+/// registro de socios que restringe quién puede aportar y mayoría (en puntos
+/// base de lo recaudado) para extender el plazo; 0 = mayoría simple.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolTerms {
+    pub milestones: Vec<Milestone>,
+    pub limits: PoolLimits,
+    pub registry: Option<u32>,
+    pub extension_quorum_bps: u32,
+}
+
+/// Propuesta abierta de extensión del plazo de un pool.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Extension {
+    pub round: u32,
+    pub proposer: Address,
+    pub new_deadline: u64,
+    pub votes_for: i128,
+}
+
+/// Registro de socios de una cooperativa.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Registry {
+    pub id: u32,
+    pub admin: Address,
+    pub members: u32,
+}
+
+/// Hito de entrega: libera `percent_bps` de lo recaudado si se confirma antes de `deadline`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Milestone {
+    pub percent_bps: u32,
+    pub deadline: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pool {
+    pub id: u32,
+    pub creator: Address,
+    pub token: Address,
+    pub supplier: Address,
+    pub goal: i128,
+    pub raised: i128,
+    pub deadline: u64,
+    pub finalized: bool,
+    pub milestones: Vec<Milestone>,
+    pub next_milestone: u32,
+    pub released: i128,
+    pub refunded: i128,
+    pub limits: PoolLimits,
+    pub members: u32,
+    pub registry: Option<u32>,
+    pub cancelled: bool,
+    pub extension_quorum_bps: u32,
+    pub extensions: u32,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    NextId,
+    Pool(u32),
+    Contribution(u32, Address),
+    NextRegistryId,
+    Registry(u32),
+    Member(u32, Address),
+    Extension(u32),
+    ExtensionVote(u32, u32, Address),
+}
+
+#[contract]
+pub struct PoolContract;
+
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_THRESHOLD, INSTANCE_BUMP);
+}
+
+fn next_id(env: &Env) -> Result<u32, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::NextId)
+        .ok_or(Error::NotInitialized)
+}
+
+fn load_pool(env: &Env, pool_id: u32) -> Result<Pool, Error> {
+    // Sin inicializar no hay pools: distinguirlo ayuda al frontend a inicializar
+    next_id(env)?;
+    let key = DataKey::Pool(pool_id);
+    let pool: Pool = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(Error::PoolNotFound)?;
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+    Ok(pool)
+}
+
+fn save_pool(env: &Env, pool: &Pool) {
+    let key = DataKey::Pool(pool.id);
+    env.storage().persistent().set(&key, pool);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+}
+
+fn contribution_of(env: &Env, pool_id: u32, who: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Contribution(pool_id, who.clone()))
+        .unwrap_or(0)
+}
+
+fn set_contribution(env: &Env, pool_id: u32, who: &Address, amount: i128) {
+    let key = DataKey::Contribution(pool_id, who.clone());
+    env.storage().persistent().set(&key, &amount);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+}
+
+fn load_registry(env: &Env, registry_id: u32) -> Result<Registry, Error> {
+    next_id(env)?;
+    let key = DataKey::Registry(registry_id);
+    let registry: Registry = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(Error::RegistryNotFound)?;
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+    Ok(registry)
+}
+
+fn save_registry(env: &Env, registry: &Registry) {
+    let key = DataKey::Registry(registry.id);
+    env.storage().persistent().set(&key, registry);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+}
+
+fn is_member_of(env: &Env, registry_id: u32, who: &Address) -> bool {
+    let key = DataKey::Member(registry_id, who.clone());
+    let member = env.storage().persistent().has(&key);
+    if member {
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+    }
+    member
+}
+
+// Carga el registro y exige que `admin` sea su administrador
+fn registry_for_admin(env: &Env, registry_id: u32, admin: &Address) -> Result<Registry, Error> {
+    admin.require_auth();
+    let registry = load_registry(env, registry_id)?;
+    if registry.admin != *admin {
+        return Err(Error::NotRegistryAdmin);
+    }
+    Ok(registry)
+}
+
+fn load_extension(env: &Env, pool_id: u32) -> Result<Extension, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Extension(pool_id))
+        .ok_or(Error::NoExtensionPending)
+}
+
+fn save_extension(env: &Env, pool_id: u32, extension: &Extension) {
+    let key = DataKey::Extension(pool_id);
+    env.storage().persistent().set(&key, extension);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+}
+
+// Se puede proponer o votar una extensión: pool abierto, sin vencer y sin llegar a la meta
+fn check_extendable(env: &Env, pool: &Pool) -> Result<(), Error> {
+    if pool.finalized {
+        return Err(Error::AlreadyFinalized);
+    }
+    if pool.cancelled {
+        return Err(Error::PoolCancelled);
+    }
+    if is_expired(env, pool) {
+        return Err(Error::PoolExpired);
+    }
+    if pool.raised >= pool.goal {
+        return Err(Error::InvalidExtension);
+    }
+    Ok(())
+}
+
+fn is_expired(env: &Env, pool: &Pool) -> bool {
+    env.ledger().timestamp() > pool.deadline
+}
+
+// Hitos: porcentajes > 0 que suman 100 % y plazos crecientes posteriores al cierre del pool
+fn check_milestones(milestones: &Vec<Milestone>, deadline: u64) -> Result<(), Error> {
+    if milestones.len() > MAX_MILESTONES {
+        return Err(Error::InvalidMilestones);
+    }
+    let mut total: u32 = 0;
+    let mut prev = deadline;
+    for m in milestones.iter() {
+        if m.percent_bps == 0 || m.deadline <= prev {
+            return Err(Error::InvalidMilestones);
+        }
+        total = total.saturating_add(m.percent_bps);
+        prev = m.deadline;
+    }
+    if !milestones.is_empty() && total != BPS_TOTAL {
+        return Err(Error::InvalidMilestones);
+    }
+    Ok(())
+}
+
+// Límites no negativos, mínimo <= tope por miembro y mínimo <= meta
+fn check_limits(limits: &PoolLimits, goal: i128) -> Result<(), Error> {
+    let min = limits.min_contribution;
+    let max = limits.max_contribution_per_member;
+    if min < 0 || max < 0 || min > goal || (max > 0 && min > max) {
+        return Err(Error::InvalidLimits);
+    }
+    Ok(())
+}
+
+// Financiado, con hitos pendientes y vencido el plazo del siguiente: el resto se reembolsa
+fn is_lapsed(env: &Env, pool: &Pool) -> bool {
+    if pool.finalized || pool.raised < pool.goal {
+        return false;
+    }
+    match pool.milestones.get(pool.next_milestone) {
+        Some(m) => env.ledger().timestamp() > m.deadline,
+        None => false,
+    }
+}
+
+#[contractimpl]
+impl PoolContract {
+    /// Prepara el contador de pools. Solo puede llamarse una vez.
+    pub fn initialize(env: Env) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::NextId) {
+            return Err(Error::AlreadyInitialized);
+        }
+        env.storage().instance().set(&DataKey::NextId, &1u32);
+        bump_instance(&env);
+        Ok(())
+    }
+
+    /// Crea un pool y devuelve su id. Sin hitos en `terms` todo se paga de una vez con
+    /// `finalize`; límites en cero dejan el pool abierto a cualquier monto y cantidad de miembros.
+    pub fn create_pool(
+        env: Env,
+        creator: Address,
+        token: Address,
+        supplier: Address,
+        goal: i128,
+        deadline: u64,
+        terms: PoolTerms,
+    ) -> Result<u32, Error> {
+        creator.require_auth();
+        if goal <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if deadline <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+        let PoolTerms {
+            milestones,
+            limits,
+            registry,
+            extension_quorum_bps,
+        } = terms;
+        check_milestones(&milestones, deadline)?;
+        check_limits(&limits, goal)?;
+        if extension_quorum_bps > BPS_TOTAL {
+            return Err(Error::InvalidExtension);
+        }
+        if let Some(registry_id) = registry {
+            load_registry(&env, registry_id)?;
+        }
+
+        let id = next_id(&env)?;
+        let pool = Pool {
+            id,
+            creator,
+            token,
+            supplier,
+            goal,
+            raised: 0,
+            deadline,
+            finalized: false,
+            milestones,
+            next_milestone: 0,
+            released: 0,
+            refunded: 0,
+            limits,
+            members: 0,
+            registry,
+            cancelled: false,
+            extension_quorum_bps: if extension_quorum_bps == 0 {
+                DEFAULT_EXTENSION_QUORUM_BPS
+            } else {
+                extension_quorum_bps
+            },
+            extensions: 0,
+        };
+        save_pool(&env, &pool);
+        env.storage().instance().set(&DataKey::NextId, &(id + 1));
+        bump_instance(&env);
+
+        env.events().publish((symbol_short!("pc"), id), pool);
+        Ok(id)
+    }
+
+    /// Aporta `amount` al pool. Requiere un `approve` previo a favor del contrato.
+    /// `members` cuenta aportantes distintos y es lo que limita `max_members`.
+    pub fn contribute(env: Env, pool_id: u32, from: Address, amount: i128) -> Result<(), Error> {
+        from.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let mut pool = load_pool(&env, pool_id)?;
+        if pool.finalized {
+            return Err(Error::AlreadyFinalized);
+        }
+        if pool.cancelled {
+            return Err(Error::PoolCancelled);
+        }
+        if is_expired(&env, &pool) {
+            return Err(Error::PoolExpired);
+        }
+        if let Some(registry_id) = pool.registry {
+            if !is_member_of(&env, registry_id, &from) {
+                return Err(Error::NotMember);
+            }
+        }
+        let remaining = pool.goal - pool.raised;
+        if amount > remaining {
+            return Err(Error::GoalExceeded);
+        }
+        // El mínimo no aplica al aporte que completa exactamente la meta
+        if amount < pool.limits.min_contribution && amount != remaining {
+            return Err(Error::BelowMinContribution);
+        }
+        let prev = contribution_of(&env, pool_id, &from);
+        let max = pool.limits.max_contribution_per_member;
+        if max > 0 && prev + amount > max {
+            return Err(Error::MemberCapExceeded);
+        }
+        let new_member = prev == 0;
+        if new_member && pool.limits.max_members > 0 && pool.members >= pool.limits.max_members {
+            return Err(Error::MaxMembersReached);
+        }
+
+        let contract = env.current_contract_address();
+        token::Client::new(&env, &pool.token).transfer_from(&contract, &from, &contract, &amount);
+
+        set_contribution(&env, pool_id, &from, prev + amount);
+        if new_member {
+            pool.members += 1;
+        }
+        pool.raised += amount;
+        save_pool(&env, &pool);
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("ctr"), pool_id, from), amount);
+        Ok(())
+    }
+
+    /// Envía lo recaudado al proveedor. Solo el creador, y solo si se llegó a la meta.
+    pub fn finalize(env: Env, pool_id: u32, creator: Address) -> Result<(), Error> {
+        creator.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        if pool.creator != creator {
+            return Err(Error::NotCreator);
+        }
+        if pool.finalized {
+            return Err(Error::AlreadyFinalized);
+        }
+        if pool.cancelled {
+            return Err(Error::PoolCancelled);
+        }
+        if !pool.milestones.is_empty() {
+            return Err(Error::HasMilestones);
+        }
+        if pool.raised < pool.goal {
+            return Err(Error::GoalNotReached);
+        }
+
+        let amount = pool.raised;
+        token::Client::new(&env, &pool.token).transfer(
+            &env.current_contract_address(),
+            &pool.supplier,
+            &amount,
+        );
+
+        pool.released = amount;
+        pool.finalized = true;
+        save_pool(&env, &pool);
+        bump_instance(&env);
+
+        env.events().publish((symbol_short!("fn"), pool_id), amount);
+        Ok(())
+    }
+
+    /// Confirma la entrega del siguiente hito y libera su porcentaje al proveedor.
+    /// El último hito libera el saldo (sin restos por redondeo) y cierra el pool.
+    pub fn release_milestone(env: Env, pool_id: u32, creator: Address) -> Result<u32, Error> {
+        creator.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        if pool.creator != creator {
+            return Err(Error::NotCreator);
+        }
+        if pool.finalized {
+            return Err(Error::AlreadyFinalized);
+        }
+        if pool.cancelled {
+            return Err(Error::PoolCancelled);
+        }
+        let index = pool.next_milestone;
+        let milestone = pool
+            .milestones
+            .get(index)
+            .ok_or(Error::NoMilestonePending)?;
+        if pool.raised < pool.goal {
+            return Err(Error::GoalNotReached);
+        }
+        if env.ledger().timestamp() > milestone.deadline {
+            return Err(Error::MilestoneLapsed);
+        }
+
+        let last = index + 1 == pool.milestones.len();
+        let amount = if last {
+            pool.raised - pool.released
+        } else {
+            pool.raised * milestone.percent_bps as i128 / BPS_TOTAL as i128
+        };
+        token::Client::new(&env, &pool.token).transfer(
+            &env.current_contract_address(),
+            &pool.supplier,
+            &amount,
+        );
+
+        pool.released += amount;
+        pool.next_milestone = index + 1;
+        pool.finalized = last;
+        save_pool(&env, &pool);
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("ms"), pool_id, index), amount);
+        if last {
+            env.events()
+                .publish((symbol_short!("fn"), pool_id), pool.released);
+        }
+        Ok(index)
+    }
+
+    /// Cancela un pool no finalizado. Solo el creador; los aportes quedan
+    /// reembolsables de inmediato, sin esperar el vencimiento.
+    pub fn cancel_pool(env: Env, pool_id: u32, creator: Address) -> Result<(), Error> {
+        creator.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        if pool.creator != creator {
+            return Err(Error::NotCreator);
+        }
+        if pool.finalized {
+            return Err(Error::AlreadyFinalized);
+        }
+        if pool.cancelled {
+            return Err(Error::PoolCancelled);
+        }
+
+        // Lo que sigue en escrow: ni liberado al proveedor ni ya reembolsado
+        let remaining = pool.raised - pool.released - pool.refunded;
+        pool.cancelled = true;
+        save_pool(&env, &pool);
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("cn"), pool_id), remaining);
+        Ok(())
+    }
+
+    /// Devuelve a `user` su aporte si el pool venció sin llegar a la meta o fue
+    /// cancelado, o su parte de lo no liberado si venció el plazo de un hito sin
+    /// confirmarse (o se canceló un pool con hitos ya financiado).
+    pub fn refund(env: Env, pool_id: u32, user: Address) -> Result<(), Error> {
+        user.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        let lapsed = is_lapsed(&env, &pool);
+        let cancelled = pool.cancelled && !pool.finalized;
+        if !lapsed
+            && !cancelled
+            && (pool.finalized || !is_expired(&env, &pool) || pool.raised >= pool.goal)
+        {
+            return Err(Error::RefundNotAvailable);
+        }
+
+        let contributed = contribution_of(&env, pool_id, &user);
+        // Pool con hitos ya financiado: `raised` queda fijo y la parte se calcula sobre lo no liberado
+        let pro_rata = !pool.milestones.is_empty() && pool.raised >= pool.goal;
+        let amount = if pro_rata {
+            contributed * (pool.raised - pool.released) / pool.raised
+        } else {
+            contributed
+        };
+        if amount <= 0 {
+            return Err(Error::NothingToRefund);
+        }
+
+        set_contribution(&env, pool_id, &user, 0);
+        if pro_rata {
+            pool.refunded += amount;
+        } else {
+            pool.raised -= amount;
+        }
+        save_pool(&env, &pool);
+
+        token::Client::new(&env, &pool.token).transfer(
+            &env.current_contract_address(),
+            &user,
+            &amount,
+        );
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("rf"), pool_id, user), amount);
+        Ok(())
+    }
+
+    /// Propone mover el plazo a `new_deadline`. Puede proponer un aportante o el
+    /// creador; solo el creador puede reemplazar una propuesta abierta (los votos
+    /// de la anterior no cuentan para la nueva). Con hitos, la nueva fecha debe
+    /// quedar antes del primer hito.
+    pub fn propose_extension(
+        env: Env,
+        pool_id: u32,
+        proposer: Address,
+        new_deadline: u64,
+    ) -> Result<u32, Error> {
+        proposer.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        check_extendable(&env, &pool)?;
+        if proposer != pool.creator && contribution_of(&env, pool_id, &proposer) <= 0 {
+            return Err(Error::NotContributor);
+        }
+        if load_extension(&env, pool_id).is_ok() && proposer != pool.creator {
+            return Err(Error::ExtensionPending);
+        }
+        if new_deadline <= pool.deadline {
+            return Err(Error::InvalidExtension);
+        }
+        if let Some(first) = pool.milestones.get(0) {
+            if new_deadline >= first.deadline {
+                return Err(Error::InvalidExtension);
+            }
+        }
+
+        let round = pool.extensions;
+        pool.extensions += 1;
+        save_pool(&env, &pool);
+        save_extension(
+            &env,
+            pool_id,
+            &Extension {
+                round,
+                proposer,
+                new_deadline,
+                votes_for: 0,
+            },
+        );
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("xp"), pool_id), new_deadline);
+        Ok(round)
+    }
+
+    /// Vota a favor de la extensión abierta con el peso del aporte de `voter`.
+    /// Devuelve `true` si con este voto se alcanzó la mayoría y el plazo se movió.
+    pub fn vote_extension(env: Env, pool_id: u32, voter: Address) -> Result<bool, Error> {
+        voter.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        check_extendable(&env, &pool)?;
+        let mut extension = load_extension(&env, pool_id)?;
+        let weight = contribution_of(&env, pool_id, &voter);
+        if weight <= 0 {
+            return Err(Error::NotContributor);
+        }
+        let vote_key = DataKey::ExtensionVote(pool_id, extension.round, voter.clone());
+        if env.storage().persistent().has(&vote_key) {
+            return Err(Error::AlreadyVoted);
+        }
+        env.storage().persistent().set(&vote_key, &weight);
+        env.storage()
+            .persistent()
+            .extend_ttl(&vote_key, POOL_THRESHOLD, POOL_BUMP);
+
+        extension.votes_for += weight;
+        env.events()
+            .publish((symbol_short!("xv"), pool_id, voter), weight);
+
+        let approved = extension.votes_for * BPS_TOTAL as i128
+            >= pool.raised * pool.extension_quorum_bps as i128;
+        if approved {
+            pool.deadline = extension.new_deadline;
+            save_pool(&env, &pool);
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Extension(pool_id));
+            env.events()
+                .publish((symbol_short!("xt"), pool_id), pool.deadline);
+        } else {
+            save_extension(&env, pool_id, &extension);
+        }
+        bump_instance(&env);
+        Ok(approved)
+    }
+
+    /// Propuesta de extensión abierta del pool.
+    pub fn get_extension(env: Env, pool_id: u32) -> Result<Extension, Error> {
+        load_pool(&env, pool_id)?;
+        load_extension(&env, pool_id)
+    }
+
+    /// Crea un registro de socios administrado por `admin` y devuelve su id.
+    pub fn create_registry(env: Env, admin: Address) -> Result<u32, Error> {
+        admin.require_auth();
+        next_id(&env)?;
+        // Contratos inicializados antes de los registros arrancan en 1
+        let id: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextRegistryId)
+            .unwrap_or(1);
+        let registry = Registry {
+            id,
+            admin: admin.clone(),
+            members: 0,
+        };
+        save_registry(&env, &registry);
+        env.storage()
+            .instance()
+            .set(&DataKey::NextRegistryId, &(id + 1));
+        bump_instance(&env);
+
+        env.events().publish((symbol_short!("rg"), id), admin);
+        Ok(id)
+    }
+
+    /// Agrega `member` al registro. Solo su administrador.
+    pub fn add_member(
+        env: Env,
+        registry_id: u32,
+        admin: Address,
+        member: Address,
+    ) -> Result<(), Error> {
+        let mut registry = registry_for_admin(&env, registry_id, &admin)?;
+        if is_member_of(&env, registry_id, &member) {
+            return Err(Error::AlreadyMember);
+        }
+
+        let key = DataKey::Member(registry_id, member.clone());
+        env.storage().persistent().set(&key, &true);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, POOL_THRESHOLD, POOL_BUMP);
+        registry.members += 1;
+        save_registry(&env, &registry);
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("ma"), registry_id, member), registry.members);
+        Ok(())
+    }
+
+    /// Quita `member` del registro. Sus aportes ya hechos no se tocan.
+    pub fn remove_member(
+        env: Env,
+        registry_id: u32,
+        admin: Address,
+        member: Address,
+    ) -> Result<(), Error> {
+        let mut registry = registry_for_admin(&env, registry_id, &admin)?;
+        if !is_member_of(&env, registry_id, &member) {
+            return Err(Error::NotMember);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Member(registry_id, member.clone()));
+        registry.members -= 1;
+        save_registry(&env, &registry);
+        bump_instance(&env);
+
+        env.events()
+            .publish((symbol_short!("mr"), registry_id, member), registry.members);
+        Ok(())
+    }
+
+    /// Lectura del registro.
+    pub fn get_registry(env: Env, registry_id: u32) -> Result<Registry, Error> {
+        load_registry(&env, registry_id)
+    }
+
+    /// `true` si `who` es socio del registro.
+    pub fn is_member(env: Env, registry_id: u32, who: Address) -> Result<bool, Error> {
+        load_registry(&env, registry_id)?;
+        Ok(is_member_of(&env, registry_id, &who))
+    }
+
+    /// Lectura del pool (para simulación desde el frontend).
+    pub fn get_pool(env: Env, pool_id: u32) -> Result<Pool, Error> {
+        load_pool(&env, pool_id)
+    }
+
+    /// Aporte vigente de `who` en el pool (0 si no aportó o ya se reembolsó).
+    pub fn get_contribution(env: Env, pool_id: u32, who: Address) -> Result<i128, Error> {
+        load_pool(&env, pool_id)?;
+        Ok(contribution_of(&env, pool_id, &who))
+    }
+}
+
+#[cfg(test)]
+mod test;
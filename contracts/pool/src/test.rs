@@ -0,0 +1,846 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::{vec, IntoVal, TryFromVal, Val, Vec};
+
+const START: u64 = 1_700_000_000;
+const DEADLINE: u64 = START + 3_600;
+const GOAL: i128 = 1_000_000_000;
+
+struct Setup<'a> {
+    env: Env,
+    pool: PoolContractClient<'a>,
+    token: token::Client<'a>,
+    creator: Address,
+    supplier: Address,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|l| l.timestamp = START);
+
+    let admin = Address::generate(&env);
+    let sac = env.register_stellar_asset_contract_v2(admin);
+    let token = token::Client::new(&env, &sac.address());
+
+    let contract_id = env.register_contract(None, PoolContract);
+    let pool = PoolContractClient::new(&env, &contract_id);
+    pool.initialize();
+
+    Setup {
+        creator: Address::generate(&env),
+        supplier: Address::generate(&env),
+        env,
+        pool,
+        token,
+    }
+}
+
+impl Setup<'_> {
+    fn member(&self, balance: i128) -> Address {
+        let member = Address::generate(&self.env);
+        token::StellarAssetClient::new(&self.env, &self.token.address).mint(&member, &balance);
+        member
+    }
+
+    fn create(&self) -> u32 {
+        self.create_with_milestones(Vec::new(&self.env))
+    }
+
+    fn create_with_milestones(&self, milestones: Vec<Milestone>) -> u32 {
+        self.create_with(milestones, no_limits())
+    }
+
+    fn create_with_limits(&self, limits: PoolLimits) -> u32 {
+        self.create_with(Vec::new(&self.env), limits)
+    }
+
+    fn create_with(&self, milestones: Vec<Milestone>, limits: PoolLimits) -> u32 {
+        self.create_terms(PoolTerms {
+            milestones,
+            limits,
+            registry: None,
+            extension_quorum_bps: 0,
+        })
+    }
+
+    fn create_terms(&self, terms: PoolTerms) -> u32 {
+        self.pool.create_pool(
+            &self.creator,
+            &self.token.address,
+            &self.supplier,
+            &GOAL,
+            &DEADLINE,
+            &terms,
+        )
+    }
+
+    fn contribute(&self, pool_id: u32, from: &Address, amount: i128) {
+        self.token.approve(
+            from,
+            &self.pool.address,
+            &amount,
+            &(self.env.ledger().sequence() + 100),
+        );
+        self.pool.contribute(&pool_id, from, &amount);
+    }
+
+    /// Último evento emitido por el contrato de pools: (tópicos, monto).
+    fn last_event(&self) -> (Vec<Val>, i128) {
+        self.last_event_as()
+    }
+
+    /// Último evento con el dato de otro tipo (p. ej. `u32` en los de registro).
+    fn last_event_as<T: TryFromVal<Env, Val>>(&self) -> (Vec<Val>, T) {
+        let (_, topics, data) = self
+            .env
+            .events()
+            .all()
+            .iter()
+            .filter(|(contract, _, _)| *contract == self.pool.address)
+            .last()
+            .unwrap();
+        (topics, T::try_from_val(&self.env, &data).ok().unwrap())
+    }
+
+    fn warp(&self, timestamp: u64) {
+        self.env.ledger().with_mut(|l| l.timestamp = timestamp);
+    }
+}
+
+#[test]
+fn create_pool_assigns_sequential_ids() {
+    let s = setup();
+    assert_eq!(s.create(), 1);
+    assert_eq!(s.create(), 2);
+
+    let pool = s.pool.get_pool(&2);
+    assert_eq!(pool.id, 2);
+    assert_eq!(pool.goal, GOAL);
+    assert_eq!(pool.raised, 0);
+    assert_eq!(pool.deadline, DEADLINE);
+    assert!(!pool.finalized);
+}
+
+#[test]
+fn uninitialized_contract_reports_not_initialized() {
+    let env = Env::default();
+    let contract_id = env.register_contract(None, PoolContract);
+    let pool = PoolContractClient::new(&env, &contract_id);
+    assert_eq!(pool.try_get_pool(&1), Err(Ok(Error::NotInitialized)));
+    pool.initialize();
+    assert_eq!(pool.try_initialize(), Err(Ok(Error::AlreadyInitialized)));
+}
+
+#[test]
+fn funded_pool_pays_supplier_on_finalize() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+
+    s.contribute(id, &alice, 600_000_000);
+    s.contribute(id, &bob, 400_000_000);
+    assert_eq!(s.pool.get_pool(&id).raised, GOAL);
+    assert_eq!(s.pool.get_contribution(&id, &alice), 600_000_000);
+    assert_eq!(s.token.balance(&s.pool.address), GOAL);
+
+    s.pool.finalize(&id, &s.creator);
+    assert_eq!(
+        s.last_event(),
+        ((symbol_short!("fn"), id).into_val(&s.env), GOAL)
+    );
+
+    assert!(s.pool.get_pool(&id).finalized);
+    assert_eq!(s.token.balance(&s.supplier), GOAL);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+}
+
+#[test]
+fn contribute_emits_ctr_event_with_contributor() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+
+    s.contribute(id, &alice, 250);
+    assert_eq!(
+        s.last_event(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("ctr").into_val(&s.env),
+                id.into_val(&s.env),
+                alice.into_val(&s.env),
+            ],
+            250
+        )
+    );
+}
+
+#[test]
+fn contribute_rejects_overfunding_and_expired_pools() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(2 * GOAL);
+
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &(GOAL + 1)),
+        Err(Ok(Error::GoalExceeded))
+    );
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    s.warp(DEADLINE + 1);
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &10),
+        Err(Ok(Error::PoolExpired))
+    );
+}
+
+#[test]
+fn finalize_requires_goal_and_creator() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+
+    s.contribute(id, &alice, GOAL / 2);
+    assert_eq!(
+        s.pool.try_finalize(&id, &s.creator),
+        Err(Ok(Error::GoalNotReached))
+    );
+
+    s.contribute(id, &alice, GOAL / 2);
+    assert_eq!(s.pool.try_finalize(&id, &alice), Err(Ok(Error::NotCreator)));
+}
+
+#[test]
+fn double_finalize_is_rejected() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+    s.contribute(id, &alice, GOAL);
+
+    s.pool.finalize(&id, &s.creator);
+    assert_eq!(
+        s.pool.try_finalize(&id, &s.creator),
+        Err(Ok(Error::AlreadyFinalized))
+    );
+    assert_eq!(s.token.balance(&s.supplier), GOAL);
+}
+
+#[test]
+fn expired_underfunded_pool_refunds_each_member_once() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    s.contribute(id, &alice, 300);
+    s.contribute(id, &bob, 200);
+
+    // Mientras está vigente no hay reembolso
+    assert_eq!(
+        s.pool.try_refund(&id, &alice),
+        Err(Ok(Error::RefundNotAvailable))
+    );
+
+    s.warp(DEADLINE + 1);
+    s.pool.refund(&id, &alice);
+    assert_eq!(s.token.balance(&alice), GOAL);
+    assert_eq!(s.pool.get_contribution(&id, &alice), 0);
+    assert_eq!(s.pool.get_pool(&id).raised, 200);
+
+    assert_eq!(
+        s.pool.try_refund(&id, &alice),
+        Err(Ok(Error::NothingToRefund))
+    );
+    assert_eq!(
+        s.pool.try_finalize(&id, &s.creator),
+        Err(Ok(Error::GoalNotReached))
+    );
+
+    s.pool.refund(&id, &bob);
+    assert_eq!(s.token.balance(&bob), GOAL);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+}
+
+fn no_limits() -> PoolLimits {
+    limits(0, 0, 0)
+}
+
+fn limits(
+    min_contribution: i128,
+    max_contribution_per_member: i128,
+    max_members: u32,
+) -> PoolLimits {
+    PoolLimits {
+        min_contribution,
+        max_contribution_per_member,
+        max_members,
+    }
+}
+
+fn milestone(percent_bps: u32, deadline: u64) -> Milestone {
+    Milestone {
+        percent_bps,
+        deadline,
+    }
+}
+
+#[test]
+fn create_pool_validates_milestones() {
+    let s = setup();
+    let bad = [
+        vec![&s.env, milestone(6_000, DEADLINE + 10)],
+        vec![
+            &s.env,
+            milestone(0, DEADLINE + 10),
+            milestone(10_000, DEADLINE + 20),
+        ],
+        vec![
+            &s.env,
+            milestone(5_000, DEADLINE + 20),
+            milestone(5_000, DEADLINE + 10),
+        ],
+        vec![&s.env, milestone(10_000, DEADLINE)],
+    ];
+    for milestones in bad {
+        assert_eq!(
+            s.pool.try_create_pool(
+                &s.creator,
+                &s.token.address,
+                &s.supplier,
+                &GOAL,
+                &DEADLINE,
+                &PoolTerms {
+                    milestones,
+                    limits: no_limits(),
+                    registry: None,
+                    extension_quorum_bps: 0
+                }
+            ),
+            Err(Ok(Error::InvalidMilestones))
+        );
+    }
+}
+
+#[test]
+fn milestones_release_in_tranches_and_close_the_pool() {
+    let s = setup();
+    let id = s.create_with_milestones(vec![
+        &s.env,
+        milestone(3_333, DEADLINE + 100),
+        milestone(6_667, DEADLINE + 200),
+    ]);
+    let alice = s.member(GOAL);
+    s.contribute(id, &alice, GOAL);
+
+    assert_eq!(
+        s.pool.try_finalize(&id, &s.creator),
+        Err(Ok(Error::HasMilestones))
+    );
+    assert_eq!(
+        s.pool.try_release_milestone(&id, &alice),
+        Err(Ok(Error::NotCreator))
+    );
+
+    assert_eq!(s.pool.release_milestone(&id, &s.creator), 0);
+    let first = GOAL * 3_333 / 10_000;
+    assert_eq!(
+        s.last_event(),
+        ((symbol_short!("ms"), id, 0u32).into_val(&s.env), first)
+    );
+    assert_eq!(s.token.balance(&s.supplier), first);
+    assert!(!s.pool.get_pool(&id).finalized);
+
+    s.warp(DEADLINE + 150);
+    assert_eq!(s.pool.release_milestone(&id, &s.creator), 1);
+    assert_eq!(
+        s.last_event(),
+        ((symbol_short!("fn"), id).into_val(&s.env), GOAL)
+    );
+
+    let pool = s.pool.get_pool(&id);
+    assert!(pool.finalized);
+    assert_eq!(pool.released, GOAL);
+    assert_eq!(s.token.balance(&s.supplier), GOAL);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+    assert_eq!(
+        s.pool.try_release_milestone(&id, &s.creator),
+        Err(Ok(Error::AlreadyFinalized))
+    );
+}
+
+#[test]
+fn lapsed_milestone_refunds_unreleased_funds_pro_rata() {
+    let s = setup();
+    let id = s.create_with_milestones(vec![
+        &s.env,
+        milestone(4_000, DEADLINE + 100),
+        milestone(6_000, DEADLINE + 200),
+    ]);
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    s.contribute(id, &alice, 750_000_000);
+    s.contribute(id, &bob, 250_000_000);
+
+    // Con la meta alcanzada no hay reembolso mientras los hitos estén al día
+    s.pool.release_milestone(&id, &s.creator);
+    s.warp(DEADLINE + 150);
+    assert_eq!(
+        s.pool.try_refund(&id, &alice),
+        Err(Ok(Error::RefundNotAvailable))
+    );
+
+    s.warp(DEADLINE + 201);
+    assert_eq!(
+        s.pool.try_release_milestone(&id, &s.creator),
+        Err(Ok(Error::MilestoneLapsed))
+    );
+
+    // Queda el 60 % en escrow: cada uno recupera el 60 % de su aporte
+    s.pool.refund(&id, &alice);
+    assert_eq!(
+        s.last_event(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("rf").into_val(&s.env),
+                id.into_val(&s.env),
+                alice.into_val(&s.env),
+            ],
+            450_000_000
+        )
+    );
+    s.pool.refund(&id, &bob);
+    assert_eq!(s.token.balance(&bob), GOAL - 100_000_000);
+    assert_eq!(
+        s.pool.try_refund(&id, &bob),
+        Err(Ok(Error::NothingToRefund))
+    );
+
+    let pool = s.pool.get_pool(&id);
+    assert_eq!(pool.raised, GOAL);
+    assert_eq!(pool.released, 400_000_000);
+    assert_eq!(pool.refunded, 600_000_000);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+}
+
+#[test]
+fn create_pool_validates_limits() {
+    let s = setup();
+    for bad in [
+        limits(-1, 0, 0),
+        limits(0, -1, 0),
+        limits(500, 100, 0),
+        limits(GOAL + 1, 0, 0),
+    ] {
+        assert_eq!(
+            s.pool.try_create_pool(
+                &s.creator,
+                &s.token.address,
+                &s.supplier,
+                &GOAL,
+                &DEADLINE,
+                &PoolTerms {
+                    milestones: Vec::new(&s.env),
+                    limits: bad,
+                    registry: None,
+                    extension_quorum_bps: 0
+                }
+            ),
+            Err(Ok(Error::InvalidLimits))
+        );
+    }
+}
+
+#[test]
+fn contribute_enforces_min_and_per_member_cap() {
+    let s = setup();
+    let id = s.create_with_limits(limits(100_000_000, 600_000_000, 0));
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &99_999_999),
+        Err(Ok(Error::BelowMinContribution))
+    );
+    s.contribute(id, &alice, 500_000_000);
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &100_000_001),
+        Err(Ok(Error::MemberCapExceeded))
+    );
+    s.contribute(id, &alice, 100_000_000);
+    s.contribute(id, &bob, 350_000_000);
+
+    // Faltan 50: se acepta aunque sea menos que el mínimo porque completa la meta
+    s.contribute(id, &bob, 50_000_000);
+    let pool = s.pool.get_pool(&id);
+    assert_eq!(pool.raised, GOAL);
+    assert_eq!(pool.members, 2);
+    assert_eq!(pool.limits, limits(100_000_000, 600_000_000, 0));
+}
+
+#[test]
+fn contribute_enforces_member_count() {
+    let s = setup();
+    let id = s.create_with_limits(limits(0, 0, 2));
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    let carol = s.member(GOAL);
+
+    s.contribute(id, &alice, 10);
+    s.contribute(id, &bob, 10);
+    assert_eq!(
+        s.pool.try_contribute(&id, &carol, &10),
+        Err(Ok(Error::MaxMembersReached))
+    );
+    // Quien ya es miembro puede seguir aportando
+    s.contribute(id, &alice, 10);
+    assert_eq!(s.pool.get_pool(&id).members, 2);
+}
+
+#[test]
+fn registry_admin_manages_members() {
+    let s = setup();
+    let admin = Address::generate(&s.env);
+    let alice = Address::generate(&s.env);
+    let id = s.pool.create_registry(&admin);
+    assert_eq!(s.pool.create_registry(&admin), id + 1);
+
+    s.pool.add_member(&id, &admin, &alice);
+    assert_eq!(
+        s.last_event_as::<u32>(),
+        ((symbol_short!("ma"), id, alice.clone()).into_val(&s.env), 1)
+    );
+    assert!(s.pool.is_member(&id, &alice));
+    assert_eq!(
+        s.pool.try_add_member(&id, &admin, &alice),
+        Err(Ok(Error::AlreadyMember))
+    );
+    assert_eq!(
+        s.pool.try_add_member(&id, &alice, &admin),
+        Err(Ok(Error::NotRegistryAdmin))
+    );
+
+    s.pool.remove_member(&id, &admin, &alice);
+    assert_eq!(
+        s.last_event_as::<u32>(),
+        ((symbol_short!("mr"), id, alice.clone()).into_val(&s.env), 0)
+    );
+    assert!(!s.pool.is_member(&id, &alice));
+    assert_eq!(
+        s.pool.try_remove_member(&id, &admin, &alice),
+        Err(Ok(Error::NotMember))
+    );
+    assert_eq!(s.pool.get_registry(&id).members, 0);
+    assert_eq!(
+        s.pool.try_get_registry(&99),
+        Err(Ok(Error::RegistryNotFound))
+    );
+}
+
+#[test]
+fn gated_pool_only_accepts_registry_members() {
+    let s = setup();
+    let admin = Address::generate(&s.env);
+    let registry = s.pool.create_registry(&admin);
+    assert_eq!(
+        s.pool.try_create_pool(
+            &s.creator,
+            &s.token.address,
+            &s.supplier,
+            &GOAL,
+            &DEADLINE,
+            &PoolTerms {
+                milestones: Vec::new(&s.env),
+                limits: no_limits(),
+                registry: Some(registry + 1),
+                extension_quorum_bps: 0
+            }
+        ),
+        Err(Ok(Error::RegistryNotFound))
+    );
+
+    let id = s.create_terms(PoolTerms {
+        milestones: Vec::new(&s.env),
+        limits: no_limits(),
+        registry: Some(registry),
+        extension_quorum_bps: 0,
+    });
+    assert_eq!(s.pool.get_pool(&id).registry, Some(registry));
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    s.pool.add_member(&registry, &admin, &alice);
+
+    s.contribute(id, &alice, 100);
+    assert_eq!(
+        s.pool.try_contribute(&id, &bob, &100),
+        Err(Ok(Error::NotMember))
+    );
+
+    // Un socio dado de baja ya no puede seguir aportando
+    s.pool.remove_member(&registry, &admin, &alice);
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &100),
+        Err(Ok(Error::NotMember))
+    );
+    assert_eq!(s.pool.get_contribution(&id, &alice), 100);
+}
+
+#[test]
+fn creator_cancels_pool_and_members_refund_before_deadline() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+    s.contribute(id, &alice, 300);
+
+    let stranger = s.member(0);
+    assert_eq!(
+        s.pool.try_cancel_pool(&id, &stranger),
+        Err(Ok(Error::NotCreator))
+    );
+
+    s.pool.cancel_pool(&id, &s.creator);
+    assert_eq!(
+        s.last_event(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("cn").into_val(&s.env),
+                id.into_val(&s.env)
+            ],
+            300
+        )
+    );
+    assert!(s.pool.get_pool(&id).cancelled);
+    assert_eq!(
+        s.pool.try_cancel_pool(&id, &s.creator),
+        Err(Ok(Error::PoolCancelled))
+    );
+    assert_eq!(
+        s.pool.try_contribute(&id, &alice, &100),
+        Err(Ok(Error::PoolCancelled))
+    );
+
+    // Sin esperar el vencimiento
+    s.pool.refund(&id, &alice);
+    assert_eq!(s.token.balance(&alice), GOAL);
+    assert_eq!(s.pool.get_pool(&id).raised, 0);
+    assert_eq!(
+        s.pool.try_finalize(&id, &s.creator),
+        Err(Ok(Error::PoolCancelled))
+    );
+}
+
+#[test]
+fn cancelled_milestone_pool_refunds_unreleased_share() {
+    let s = setup();
+    let id = s.create_with_milestones(vec![
+        &s.env,
+        milestone(4_000, DEADLINE + 100),
+        milestone(6_000, DEADLINE + 200),
+    ]);
+    let alice = s.member(GOAL);
+    s.contribute(id, &alice, GOAL);
+    s.pool.release_milestone(&id, &s.creator);
+
+    s.pool.cancel_pool(&id, &s.creator);
+    assert_eq!(s.last_event().1, 600_000_000);
+    assert_eq!(
+        s.pool.try_release_milestone(&id, &s.creator),
+        Err(Ok(Error::PoolCancelled))
+    );
+
+    s.pool.refund(&id, &alice);
+    let pool = s.pool.get_pool(&id);
+    assert_eq!(pool.raised, GOAL);
+    assert_eq!(pool.refunded, 600_000_000);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+
+    // Uno finalizado ya no se puede cancelar
+    let done = s.create();
+    s.contribute(done, &s.member(GOAL), GOAL);
+    s.pool.finalize(&done, &s.creator);
+    assert_eq!(
+        s.pool.try_cancel_pool(&done, &s.creator),
+        Err(Ok(Error::AlreadyFinalized))
+    );
+}
+
+#[test]
+fn cancel_event_excludes_refunds_already_paid() {
+    let s = setup();
+    let id = s.create_with_milestones(vec![
+        &s.env,
+        milestone(4_000, DEADLINE + 100),
+        milestone(6_000, DEADLINE + 200),
+    ]);
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    s.contribute(id, &alice, 750_000_000);
+    s.contribute(id, &bob, 250_000_000);
+    s.pool.release_milestone(&id, &s.creator);
+
+    // Hito vencido: alice ya retiró su parte (450) antes de la cancelación
+    s.warp(DEADLINE + 201);
+    s.pool.refund(&id, &alice);
+    s.pool.cancel_pool(&id, &s.creator);
+    assert_eq!(
+        s.last_event(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("cn").into_val(&s.env),
+                id.into_val(&s.env)
+            ],
+            150_000_000
+        )
+    );
+
+    s.pool.refund(&id, &bob);
+    assert_eq!(s.token.balance(&s.pool.address), 0);
+}
+
+#[test]
+fn weighted_majority_extends_the_deadline() {
+    let s = setup();
+    let id = s.create();
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    let carol = s.member(GOAL);
+    s.contribute(id, &alice, 500);
+    s.contribute(id, &bob, 300);
+    s.contribute(id, &carol, 200);
+
+    let stranger = s.member(GOAL);
+    assert_eq!(
+        s.pool
+            .try_propose_extension(&id, &stranger, &(DEADLINE + 600)),
+        Err(Ok(Error::NotContributor))
+    );
+    assert_eq!(
+        s.pool.try_propose_extension(&id, &bob, &DEADLINE),
+        Err(Ok(Error::InvalidExtension))
+    );
+
+    assert_eq!(s.pool.propose_extension(&id, &bob, &(DEADLINE + 600)), 0);
+    assert_eq!(
+        s.last_event_as::<u64>(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("xp").into_val(&s.env),
+                id.into_val(&s.env)
+            ],
+            DEADLINE + 600
+        )
+    );
+    // Un aportante no puede pisar una propuesta abierta
+    assert_eq!(
+        s.pool.try_propose_extension(&id, &carol, &(DEADLINE + 900)),
+        Err(Ok(Error::ExtensionPending))
+    );
+
+    // 300 + 200 de 1000 no alcanzan la mayoría simple
+    assert!(!s.pool.vote_extension(&id, &bob));
+    assert_eq!(
+        s.pool.try_vote_extension(&id, &bob),
+        Err(Ok(Error::AlreadyVoted))
+    );
+    assert!(!s.pool.vote_extension(&id, &carol));
+    assert_eq!(s.pool.get_extension(&id).votes_for, 500);
+    assert_eq!(s.pool.get_pool(&id).deadline, DEADLINE);
+
+    assert!(s.pool.vote_extension(&id, &alice));
+    assert_eq!(
+        s.last_event_as::<u64>(),
+        (
+            vec![
+                &s.env,
+                symbol_short!("xt").into_val(&s.env),
+                id.into_val(&s.env)
+            ],
+            DEADLINE + 600
+        )
+    );
+    assert_eq!(s.pool.get_pool(&id).deadline, DEADLINE + 600);
+    assert_eq!(
+        s.pool.try_get_extension(&id),
+        Err(Ok(Error::NoExtensionPending))
+    );
+
+    // Con el plazo nuevo se sigue aportando y no hay reembolso
+    s.warp(DEADLINE + 1);
+    s.contribute(id, &alice, 100);
+    assert_eq!(
+        s.pool.try_refund(&id, &bob),
+        Err(Ok(Error::RefundNotAvailable))
+    );
+}
+
+#[test]
+fn extension_respects_configured_quorum_and_milestones() {
+    let s = setup();
+    let id = s.create_terms(PoolTerms {
+        milestones: vec![&s.env, milestone(10_000, DEADLINE + 1_000)],
+        limits: no_limits(),
+        registry: None,
+        extension_quorum_bps: 9_000,
+    });
+    let alice = s.member(GOAL);
+    let bob = s.member(GOAL);
+    s.contribute(id, &alice, 800);
+    s.contribute(id, &bob, 200);
+
+    // La nueva fecha no puede pasar el primer hito
+    assert_eq!(
+        s.pool
+            .try_propose_extension(&id, &s.creator, &(DEADLINE + 1_000)),
+        Err(Ok(Error::InvalidExtension))
+    );
+    s.pool.propose_extension(&id, &alice, &(DEADLINE + 500));
+    assert!(!s.pool.vote_extension(&id, &alice));
+
+    // El creador reemplaza la propuesta: los votos anteriores no cuentan
+    assert_eq!(
+        s.pool.propose_extension(&id, &s.creator, &(DEADLINE + 700)),
+        1
+    );
+    assert_eq!(s.pool.get_extension(&id).votes_for, 0);
+    assert!(!s.pool.vote_extension(&id, &alice));
+    assert!(s.pool.vote_extension(&id, &bob));
+    assert_eq!(s.pool.get_pool(&id).deadline, DEADLINE + 700);
+
+    // Vencido ya no se puede proponer
+    s.warp(DEADLINE + 701);
+    assert_eq!(
+        s.pool.try_propose_extension(&id, &alice, &(DEADLINE + 900)),
+        Err(Ok(Error::PoolExpired))
+    );
+    assert_eq!(
+        s.pool.try_create_pool(
+            &s.creator,
+            &s.token.address,
+            &s.supplier,
+            &GOAL,
+            &(DEADLINE + 2_000),
+            &PoolTerms {
+                milestones: Vec::new(&s.env),
+                limits: no_limits(),
+                registry: None,
+                extension_quorum_bps: 10_001,
+            },
+        ),
+        Err(Ok(Error::InvalidExtension))
+    );
+}
+
+#[test]
+fn unknown_pool_is_reported() {
+    let s = setup();
+    assert_eq!(s.pool.try_get_pool(&42), Err(Ok(Error::PoolNotFound)));
+}
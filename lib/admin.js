@@ -0,0 +1,145 @@
+const crypto = require('crypto');
+const { StrKey } = require('@stellar/stellar-sdk');
+
+// Administración por roles. Una cuenta admin es:
+//   - una dirección Stellar: entra con la sesión firmada de lib/auth (Authorization: Bearer)
+//   - una credencial "nombre:secreto" (header X-Admin-Key); solo se guarda el hash scrypt
+// ADMIN_CODE queda como superadmin de arranque, solo por header (X-Admin-Code), nunca por query/body.
+
+const ROLES = { auditor: 1, moderator: 2, superadmin: 3 };
+
+class AdminError extends Error {
+    constructor(message, status = 400) {
+        super(message);
+        this.status = status;
+    }
+}
+
+function hashSecret(secret) {
+    const salt = crypto.randomBytes(16);
+    const hash = crypto.scryptSync(String(secret), salt, 32);
+    return `scrypt$${salt.toString('base64url')}$${hash.toString('base64url')}`;
+}
+
+function verifySecret(secret, stored) {
+    const [alg, salt, hash] = String(stored || '').split('$');
+    if (alg !== 'scrypt' || !salt || !hash) return false;
+    const expected = Buffer.from(hash, 'base64url');
+    const got = crypto.scryptSync(String(secret), Buffer.from(salt, 'base64url'), expected.length);
+    return crypto.timingSafeEqual(got, expected);
+}
+
+function sameString(a, b) {
+    const x = Buffer.from(String(a));
+    const y = Buffer.from(String(b));
+    return x.length === y.length && crypto.timingSafeEqual(x, y);
+}
+
+// Campos del body que nunca van al audit
+const SECRET_FIELDS = ['secret', 'code', 'password', 'token'];
+
+function auditDetails(req) {
+    const body = req.body && typeof req.body === 'object' ? { ...req.body } : {};
+    for (const k of SECRET_FIELDS) delete body[k];
+    return { method: req.method, path: req.path, ...(Object.keys(body).length ? { body } : {}) };
+}
+
+// ADMIN_ADDRESSES=G...:superadmin,G...:auditor (sin rol = moderator)
+function parseAdminAddresses(value) {
+    return String(value || '').split(',').map(s => s.trim()).filter(Boolean).map(entry => {
+        const [id, role = 'moderator'] = entry.split(':');
+        if (!StrKey.isValidEd25519PublicKey(id)) throw new Error(`ADMIN_ADDRESSES: dirección inválida ${id}`);
+        if (!ROLES[role]) throw new Error(`ADMIN_ADDRESSES: rol desconocido ${role}`);
+        return { id, role };
+    });
+}
+
+function createAdmin({ store, auth, legacyCode = '', seed = [], logger = null }) {
+    // Las direcciones de entorno se agregan si faltan; no pisan cambios hechos por API
+    for (const a of seed) {
+        if (!store.getAdminAccount(a.id)) store.saveAdminAccount({ ...a, kind: 'address', createdBy: 'env' });
+    }
+
+    // Quién hace la request: { actor, role } o null
+    function identify(req) {
+        const code = req.headers['x-admin-code'];
+        if (legacyCode && code && sameString(code, legacyCode)) return { actor: 'admin-code', role: 'superadmin' };
+
+        const key = String(req.headers['x-admin-key'] || code || '');
+        const i = key.indexOf(':');
+        if (i > 0) {
+            const acct = store.getAdminAccount(key.slice(0, i));
+            if (acct && acct.kind === 'credential' && verifySecret(key.slice(i + 1), acct.secret_hash)) {
+                return { actor: acct.id, role: acct.role };
+            }
+        }
+
+        const address = auth.authenticate(auth.tokenFrom(req));
+        if (address) {
+            const acct = store.getAdminAccount(address);
+            if (acct && acct.kind === 'address') return { actor: address, role: acct.role };
+        }
+        return null;
+    }
+
+    function audit(req, action, outcome, extra = {}) {
+        try {
+            store.appendAudit({
+                actor: req.admin?.actor ?? null,
+                role: req.admin?.role ?? null,
+                action,
+                target: extra.target ?? req.params?.id ?? req.body?.id ?? null,
+                outcome,
+                status: extra.status ?? null,
+                details: extra.details ?? auditDetails(req),
+                requestId: req.id ?? null
+            });
+        } catch (e) {
+            logger?.error('no se pudo escribir el audit', { operation: 'ADMIN', action, error: String(e) });
+        }
+    }
+
+    // Middleware: exige al menos `minRole` y deja una fila de audit por request (ok, denied o error)
+    function requireRole(minRole, action) {
+        return (req, res, next) => {
+            const who = identify(req);
+            if (!who || ROLES[who.role] < ROLES[minRole]) {
+                req.admin = who;
+                audit(req, action, 'denied', { status: 403 });
+                req.log?.warn('acción admin denegada', { operation: 'ADMIN', action, actor: who?.actor ?? null });
+                return res.status(403).json({ error: 'forbidden' });
+            }
+            req.admin = who;
+            res.on('finish', () => {
+                audit(req, action, res.statusCode < 400 ? 'ok' : 'error', { status: res.statusCode });
+            });
+            next();
+        };
+    }
+
+    // Alta de cuenta: dirección G... o credencial (el secreto se devuelve una sola vez)
+    function createAccount({ address = null, name = null, role }, createdBy) {
+        if (!ROLES[role]) throw new AdminError(`role must be one of ${Object.keys(ROLES).join(', ')}`);
+        if (address) {
+            if (!StrKey.isValidEd25519PublicKey(String(address))) throw new AdminError('address must be a G... account');
+            store.saveAdminAccount({ id: String(address), kind: 'address', role, createdBy });
+            return { id: String(address), kind: 'address', role };
+        }
+        if (!/^[a-z0-9][a-z0-9._-]{2,31}$/i.test(String(name || ''))) {
+            throw new AdminError('name must be 3-32 chars [a-z0-9._-]');
+        }
+        if (StrKey.isValidEd25519PublicKey(String(name))) throw new AdminError('use address for Stellar accounts');
+        const secret = crypto.randomBytes(24).toString('base64url');
+        store.saveAdminAccount({ id: String(name), kind: 'credential', role, secretHash: hashSecret(secret), createdBy });
+        return { id: String(name), kind: 'credential', role, key: `${name}:${secret}` };
+    }
+
+    function removeAccount(id, by) {
+        if (String(id) === String(by)) throw new AdminError('cannot remove your own account', 409);
+        if (!store.deleteAdminAccount(id)) throw new AdminError('account not found', 404);
+    }
+
+    return { identify, requireRole, audit, createAccount, removeAccount, listAccounts: () => store.listAdminAccounts() };
+}
+
+module.exports = { ROLES, AdminError, hashSecret, verifySecret, parseAdminAddresses, createAdmin };
@@ -0,0 +1,97 @@
+// Reparto pro-rata de lo comprado por una pool entre sus aportantes.
+// Todo en BigInt (i128): nada pasa por Number, así que no hay errores de coma flotante.
+
+// Cantidades con decimales (kg, m3...) se reparten en milésimas
+const QUANTITY_SCALE = 1000n;
+const PPM = 1_000_000n;
+
+// Reparte `total` unidades enteras en proporción a `weights` (largest remainder / Hamilton).
+// Regla del resto, determinista: cada unidad sobrante va al mayor resto; empate -> mayor aporte;
+// empate -> dirección ascendente. La suma de lo repartido es exactamente `total`.
+function apportion(weights, total) {
+    const sum = weights.reduce((acc, w) => acc + w.weight, 0n);
+    if (sum <= 0n || total <= 0n) return weights.map(w => ({ key: w.key, units: 0n }));
+
+    const rows = weights.map(w => ({
+        key: w.key,
+        weight: w.weight,
+        units: (total * w.weight) / sum,
+        remainder: (total * w.weight) % sum
+    }));
+    let left = total - rows.reduce((acc, r) => acc + r.units, 0n);
+
+    const order = [...rows].sort((a, b) =>
+        (a.remainder !== b.remainder ? (b.remainder > a.remainder ? 1 : -1) : 0)
+        || (a.weight !== b.weight ? (b.weight > a.weight ? 1 : -1) : 0)
+        || (a.key < b.key ? -1 : a.key > b.key ? 1 : 0));
+    for (const r of order) {
+        if (left <= 0n) break;
+        r.units += 1n;
+        left -= 1n;
+    }
+    return rows.map(r => ({ key: r.key, units: r.units }));
+}
+
+// 12.5 -> 12500n (milésimas); null si no es un número positivo
+function toScaled(q) {
+    const n = Number(q);
+    if (!Number.isFinite(n) || n <= 0) return null;
+    return BigInt(Math.round(n * Number(QUANTITY_SCALE)));
+}
+
+function scaledToString(units) {
+    const whole = units / QUANTITY_SCALE;
+    const frac = (units % QUANTITY_SCALE).toString().padStart(3, '0').replace(/0+$/, '');
+    return frac ? `${whole}.${frac}` : whole.toString();
+}
+
+// totals: Map(address -> aporte neto BigInt). metadata: la de lib/metadata (opcional).
+function computeAllocation(totals, metadata = null) {
+    const members = [...totals]
+        .filter(([, amount]) => amount > 0n)
+        .map(([address, amount]) => ({ key: address, weight: amount }))
+        .sort((a, b) => (a.key < b.key ? -1 : a.key > b.key ? 1 : 0));
+    const total = members.reduce((acc, m) => acc + m.weight, 0n);
+
+    const quantity = toScaled(metadata?.targetQuantity);
+    const price = metadata?.pricePerUnitClp != null ? BigInt(metadata.pricePerUnitClp) : null;
+    // Valor total en CLP: precio * cantidad (redondeado a peso entero)
+    const totalClp = price != null && quantity != null
+        ? (price * quantity + QUANTITY_SCALE / 2n) / QUANTITY_SCALE
+        : null;
+
+    const ppm = new Map(apportion(members, PPM).map(r => [r.key, r.units]));
+    const qty = quantity != null ? new Map(apportion(members, quantity).map(r => [r.key, r.units])) : null;
+    const clp = totalClp != null ? new Map(apportion(members, totalClp).map(r => [r.key, r.units])) : null;
+
+    return {
+        totalContributed: total.toString(),
+        unit: metadata?.unit ?? null,
+        totalQuantity: quantity != null ? scaledToString(quantity) : null,
+        totalValueClp: totalClp != null ? totalClp.toString() : null,
+        rounding: 'largest-remainder; ties: larger contribution, then address ascending',
+        members: members.map(m => ({
+            address: m.key,
+            contributed: m.weight.toString(),
+            sharePpm: ppm.get(m.key).toString(),
+            quantity: qty ? scaledToString(qty.get(m.key)) : null,
+            valueClp: clp ? clp.get(m.key).toString() : null
+        }))
+    };
+}
+
+function csvCell(v) {
+    const s = v == null ? '' : String(v);
+    return /[",\n]/.test(s) ? `"${s.replace(/"/g, '""')}"` : s;
+}
+
+function allocationToCsv(allocation) {
+    const header = ['address', 'contributed_stroops', 'share_ppm', 'quantity', 'unit', 'value_clp'];
+    const lines = [header.join(',')];
+    for (const m of allocation.members) {
+        lines.push([m.address, m.contributed, m.sharePpm, m.quantity, allocation.unit, m.valueClp].map(csvCell).join(','));
+    }
+    return lines.join('\n') + '\n';
+}
+
+module.exports = { QUANTITY_SCALE, apportion, computeAllocation, allocationToCsv };
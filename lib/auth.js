@@ -0,0 +1,104 @@
+const crypto = require('crypto');
+const { Keypair, StrKey } = require('@stellar/stellar-sdk');
+
+// Prefijo SEP-53 que antepone Freighter (signMessage) antes de hashear
+const SIGNED_MESSAGE_PREFIX = 'Stellar Signed Message:\n';
+
+const sha256 = (buf) => crypto.createHash('sha256').update(buf).digest();
+
+function decodeSignature(sig) {
+    if (!sig) return null;
+    if (typeof sig === 'object' && Array.isArray(sig.data)) return Buffer.from(sig.data); // Buffer serializado
+    const s = String(sig).trim();
+    if (/^[0-9a-f]{128}$/i.test(s)) return Buffer.from(s, 'hex');
+    const b = Buffer.from(s, 'base64');
+    return b.length === 64 ? b : null;
+}
+
+// Verifica una firma ed25519 de `message` hecha con la clave de `address`.
+// Acepta el formato SEP-53 (Freighter actual) y los formatos previos (sha256 del mensaje o mensaje crudo).
+function verifySignedMessage(address, message, signature) {
+    if (!StrKey.isValidEd25519PublicKey(String(address || ''))) return false;
+    const sig = decodeSignature(signature);
+    if (!sig) return false;
+    const kp = Keypair.fromPublicKey(address);
+    const msg = Buffer.from(String(message), 'utf8');
+    const candidates = [
+        sha256(Buffer.concat([Buffer.from(SIGNED_MESSAGE_PREFIX, 'utf8'), msg])),
+        sha256(msg),
+        msg
+    ];
+    return candidates.some(data => {
+        try { return kp.verify(data, sig); } catch (_) { return false; }
+    });
+}
+
+class AuthError extends Error {
+    constructor(message, status = 401) {
+        super(message);
+        this.status = status;
+    }
+}
+
+// Challenge/response: el servidor entrega un nonce, el cliente lo firma con su clave
+// Stellar (Freighter) y recibe un token de sesión ligado a esa dirección.
+function createAuth({ store, nonceTtlMs = 5 * 60_000, sessionTtlMs = 12 * 3600_000, domain = 'AgroCoop' } = {}) {
+    const nonces = new Map(); // nonce -> { address, message, expiresAt }
+
+    function purgeNonces(now = Date.now()) {
+        for (const [n, c] of nonces) if (c.expiresAt <= now) nonces.delete(n);
+    }
+
+    function challenge(address) {
+        if (!StrKey.isValidEd25519PublicKey(String(address || ''))) {
+            throw new AuthError('invalid address', 400);
+        }
+        purgeNonces();
+        const nonce = crypto.randomBytes(16).toString('hex');
+        const expiresAt = Date.now() + nonceTtlMs;
+        const message = `${domain} login\nAddress: ${address}\nNonce: ${nonce}\nExpires: ${new Date(expiresAt).toISOString()}`;
+        nonces.set(nonce, { address, message, expiresAt });
+        return { nonce, message, expiresAt: new Date(expiresAt).toISOString() };
+    }
+
+    function verify({ address, nonce, signature }) {
+        const c = nonces.get(String(nonce || ''));
+        // Un nonce sirve una sola vez, salga bien o mal
+        nonces.delete(String(nonce || ''));
+        if (!c || c.expiresAt <= Date.now()) throw new AuthError('unknown or expired nonce');
+        if (c.address !== address) throw new AuthError('nonce issued for another address');
+        if (!verifySignedMessage(address, c.message, signature)) throw new AuthError('invalid signature');
+
+        const token = crypto.randomBytes(32).toString('hex');
+        const expiresAt = Date.now() + sessionTtlMs;
+        store.saveSession(sha256(token).toString('hex'), address, expiresAt);
+        return { token, address, expiresAt: new Date(expiresAt).toISOString() };
+    }
+
+    // Dirección asociada al token (o null si no existe / venció)
+    function authenticate(token) {
+        if (!token) return null;
+        return store.getSessionAddress(sha256(String(token)).toString('hex'), Date.now());
+    }
+
+    function logout(token) {
+        if (token) store.deleteSession(sha256(String(token)).toString('hex'));
+    }
+
+    function tokenFrom(req) {
+        const h = String(req.headers.authorization || '');
+        return h.startsWith('Bearer ') ? h.slice(7).trim() : null;
+    }
+
+    // Middleware: exige sesión firmada y deja la dirección en req.auth.address
+    function requireAuth(req, res, next) {
+        const address = authenticate(tokenFrom(req));
+        if (!address) return res.status(401).json({ error: 'authentication required' });
+        req.auth = { address };
+        next();
+    }
+
+    return { challenge, verify, authenticate, logout, tokenFrom, requireAuth };
+}
+
+module.exports = { AuthError, createAuth, verifySignedMessage };
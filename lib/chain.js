@@ -0,0 +1,173 @@
+const StellarSdk = require('@stellar/stellar-sdk');
+
+// Campos que viven en el contrato: el cliente nunca los impone
+const CHAIN_FIELDS = ['creator', 'supplier', 'token', 'goal', 'raised', 'deadline', 'finalized', 'cancelled'];
+
+// Hitos de entrega del contrato ({ percent_bps, deadline }) con números planos
+function normalizeMilestones(list) {
+    return Array.isArray(list)
+        ? list.map(m => ({ percent_bps: Number(m.percent_bps), deadline: Number(m.deadline) }))
+        : [];
+}
+
+// Límites de aporte (0 = sin límite): i128 como string, como goal/raised
+function normalizeLimits(l) {
+    return {
+        min_contribution: String(l?.min_contribution ?? 0),
+        max_contribution_per_member: String(l?.max_contribution_per_member ?? 0),
+        max_members: Number(l?.max_members ?? 0)
+    };
+}
+
+// Pool tal como la guarda el backend (strings para i128, número para u64)
+function normalizeChainPool(p) {
+    return {
+        id: Number(p.id),
+        creator: String(p.creator),
+        supplier: String(p.supplier),
+        token: String(p.token),
+        goal: String(p.goal),
+        raised: String(p.raised),
+        deadline: Number(p.deadline),
+        finalized: Boolean(p.finalized),
+        milestones: normalizeMilestones(p.milestones),
+        next_milestone: Number(p.next_milestone ?? 0),
+        released: String(p.released ?? 0),
+        refunded: String(p.refunded ?? 0),
+        limits: normalizeLimits(p.limits),
+        members: Number(p.members ?? 0),
+        registry: p.registry == null ? null : Number(p.registry),
+        cancelled: Boolean(p.cancelled),
+        extension_quorum_bps: Number(p.extension_quorum_bps ?? 0),
+        extensions: Number(p.extensions ?? 0)
+    };
+}
+
+function sameValue(field, a, b) {
+    if (field === 'finalized' || field === 'cancelled') return Boolean(a) === Boolean(b);
+    if (field === 'deadline') return Number(a) === Number(b);
+    return String(a) === String(b);
+}
+
+// Compara lo enviado por el cliente con get_pool. Solo reporta campos presentes en el payload.
+function diffPool(submitted, onchain) {
+    const diff = {};
+    for (const f of CHAIN_FIELDS) {
+        if (submitted[f] === undefined || submitted[f] === null) continue;
+        if (!sameValue(f, submitted[f], onchain[f])) {
+            diff[f] = { submitted: submitted[f], onchain: onchain[f] };
+        }
+    }
+    return diff;
+}
+
+// Lecturas por simulación (no firma ni gasta fee): get_pool de cualquier contrato indexado
+// y decimals/symbol de un token SAC
+function createChainReader({ rpcUrl, contractId, networkPassphrase }) {
+    const server = new StellarSdk.SorobanRpc.Server(rpcUrl, { allowHttp: true });
+    // Para simular basta una cuenta cualquiera; no necesita existir en la red
+    const source = new StellarSdk.Account(StellarSdk.Keypair.random().publicKey(), '0');
+
+    async function simulate(contract, fn, ...args) {
+        const tx = new StellarSdk.TransactionBuilder(source, {
+            fee: StellarSdk.BASE_FEE,
+            networkPassphrase
+        })
+            .addOperation(new StellarSdk.Contract(contract).call(fn, ...args))
+            .setTimeout(30)
+            .build();
+        return server.simulateTransaction(tx);
+    }
+
+    async function getPool(poolId, contract = contractId) {
+        const sim = await simulate(contract, 'get_pool', StellarSdk.nativeToScVal(Number(poolId), { type: 'u32' }));
+        if (StellarSdk.SorobanRpc.Api.isSimulationError(sim)) {
+            // Error(Contract, #3) = PoolNotFound
+            if (/Error\(Contract, #3\)/.test(String(sim.error))) return null;
+            throw new Error(`get_pool simulation failed: ${sim.error}`);
+        }
+        const retval = sim.result?.retval;
+        if (!retval) throw new Error('get_pool simulation returned no value');
+        return { ...normalizeChainPool(StellarSdk.scValToNative(retval)), contract };
+    }
+
+    async function getToken(tokenId) {
+        const read = async (fn) => {
+            const sim = await simulate(tokenId, fn);
+            if (StellarSdk.SorobanRpc.Api.isSimulationError(sim) || !sim.result?.retval) {
+                throw new Error(`${fn} simulation failed: ${sim.error || 'no value'}`);
+            }
+            return StellarSdk.scValToNative(sim.result.retval);
+        };
+        const [decimals, symbol] = await Promise.all([read('decimals'), read('symbol')]);
+        return { decimals: Number(decimals), symbol: String(symbol) };
+    }
+
+    return { kind: 'rpc', getPool, getToken };
+}
+
+// Sin RPC (fixtures): el estado indexado desde eventos hace de cadena.
+// `keyOf(contract, id)` da la clave del Map (lib/pool-keys.js).
+function createCacheChainReader(pools, keyOf = (_contract, id) => String(id)) {
+    return {
+        kind: 'cache',
+        getPool: async (poolId, contract = null) => {
+            const p = pools.get(keyOf(contract, poolId));
+            return p && p.creator ? { ...normalizeChainPool(p), contract: p.contract ?? contract } : null;
+        },
+        getToken: async () => null
+    };
+}
+
+// Si el token no se puede leer: 7 decimales (los de todo SAC de activo clásico) y sin símbolo
+const DEFAULT_TOKEN = { decimals: 7, symbol: null };
+
+// Decimales y símbolo por token, cacheados; el SAC nativo se conoce sin consultar
+function createTokenRegistry({ reader, nativeTokenId = null, logger = null }) {
+    const cache = new Map();
+    if (nativeTokenId) cache.set(nativeTokenId, { decimals: 7, symbol: 'XLM' });
+
+    async function resolve(tokenId) {
+        if (!tokenId) return DEFAULT_TOKEN;
+        if (cache.has(tokenId)) return cache.get(tokenId);
+        try {
+            const t = await reader.getToken(tokenId);
+            if (!t) return DEFAULT_TOKEN;
+            // El SAC de XLM responde "native"
+            const info = { decimals: t.decimals, symbol: t.symbol === 'native' ? 'XLM' : t.symbol };
+            cache.set(tokenId, info);
+            return info;
+        } catch (e) {
+            logger?.warn('no se pudo leer el token', { operation: 'TOKEN', tokenId, error: String(e.message || e) });
+            return DEFAULT_TOKEN;
+        }
+    }
+
+    // Adjunta tokenDecimals/tokenSymbol a cada pool
+    async function annotate(list) {
+        const ids = [...new Set(list.map(p => p.token).filter(Boolean))];
+        const infos = new Map(await Promise.all(ids.map(async id => [id, await resolve(id)])));
+        return list.map(p => {
+            const t = infos.get(p.token) || DEFAULT_TOKEN;
+            return { ...p, tokenDecimals: t.decimals, tokenSymbol: t.symbol };
+        });
+    }
+
+    // Tokens ofrecidos al crear una pool, con sus decimales y símbolo
+    async function list(ids) {
+        return Promise.all([...new Set(ids.filter(Boolean))].map(async id => ({ id, ...await resolve(id) })));
+    }
+
+    return { resolve, annotate, list };
+}
+
+module.exports = {
+    CHAIN_FIELDS,
+    normalizeMilestones,
+    normalizeLimits,
+    normalizeChainPool,
+    diffPool,
+    createChainReader,
+    createCacheChainReader,
+    createTokenRegistry
+};
@@ -0,0 +1,877 @@
+const fs = require('fs');
+const path = require('path');
+const Database = require('better-sqlite3');
+
+// Campos de pool que viven en columnas propias; el resto va a `extra` (JSON)
+const POOL_COLUMNS = ['key', 'contract', 'id', 'name', 'creator', 'supplier', 'token', 'goal', 'raised', 'deadline', 'finalized'];
+
+const SCHEMA = `
+CREATE TABLE IF NOT EXISTS pools (
+    key        TEXT PRIMARY KEY,
+    contract   TEXT,
+    id         INTEGER NOT NULL,
+    name       TEXT NOT NULL DEFAULT '',
+    creator    TEXT,
+    supplier   TEXT,
+    token      TEXT,
+    goal       TEXT NOT NULL DEFAULT '0',
+    raised     TEXT NOT NULL DEFAULT '0',
+    deadline   INTEGER NOT NULL DEFAULT 0,
+    finalized  INTEGER NOT NULL DEFAULT 0,
+    extra      TEXT NOT NULL DEFAULT '{}',
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contributions (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id     TEXT NOT NULL,
+    contributor TEXT NOT NULL,
+    amount      TEXT NOT NULL,
+    ledger      INTEGER,
+    tx_hash     TEXT,
+    timestamp   TEXT,
+    event_id    TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_contributions_pool ON contributions(pool_id);
+CREATE INDEX IF NOT EXISTS idx_contributions_contributor ON contributions(contributor);
+
+CREATE TABLE IF NOT EXISTS refunds (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id   TEXT NOT NULL,
+    address   TEXT NOT NULL,
+    amount    TEXT NOT NULL,
+    ledger    INTEGER,
+    tx_hash   TEXT,
+    timestamp TEXT,
+    event_id  TEXT UNIQUE
+);
+CREATE INDEX IF NOT EXISTS idx_refunds_pool ON refunds(pool_id, address);
+
+CREATE TABLE IF NOT EXISTS events (
+    id         TEXT PRIMARY KEY,
+    applied_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pool_metadata (
+    pool_id            TEXT PRIMARY KEY,
+    category           TEXT,
+    unit               TEXT,
+    price_per_unit_clp TEXT,
+    target_quantity    REAL,
+    delivery_location  TEXT,
+    description        TEXT,
+    updated_by         TEXT,
+    updated_at         TEXT NOT NULL,
+    xlm_clp_rate       REAL,
+    rate_source        TEXT,
+    rate_updated_at    TEXT
+);
+
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    token_hash TEXT PRIMARY KEY,
+    address    TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS hidden (
+    id TEXT PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS meta (
+    key   TEXT PRIMARY KEY,
+    value TEXT
+);
+
+CREATE TABLE IF NOT EXISTS vouchers (
+    code        TEXT PRIMARY KEY,
+    pool_id     TEXT NOT NULL,
+    contributor TEXT NOT NULL,
+    supplier    TEXT NOT NULL,
+    amount      TEXT NOT NULL,
+    status      TEXT NOT NULL DEFAULT 'issued',
+    issued_at   TEXT NOT NULL,
+    redeemed_at TEXT,
+    redeemed_by TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_vouchers_member ON vouchers(pool_id, contributor);
+
+CREATE TABLE IF NOT EXISTS tx_tracking (
+    hash            TEXT PRIMARY KEY,
+    fn              TEXT,
+    source          TEXT,
+    status          TEXT NOT NULL,
+    ledger          INTEGER,
+    error           TEXT,
+    callback_status TEXT,
+    created_at      TEXT NOT NULL,
+    updated_at      TEXT NOT NULL,
+    settled_at      TEXT
+);
+
+CREATE TABLE IF NOT EXISTS subscriptions (
+    address     TEXT PRIMARY KEY,
+    email       TEXT,
+    webhook_url TEXT,
+    kinds       TEXT,
+    created_at  TEXT NOT NULL,
+    updated_at  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id  TEXT NOT NULL,
+    kind     TEXT NOT NULL,
+    address  TEXT NOT NULL,
+    channel  TEXT NOT NULL,
+    status   TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error    TEXT,
+    sent_at  TEXT NOT NULL,
+    UNIQUE (pool_id, kind, address, channel)
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_address ON notifications(address);
+
+CREATE TABLE IF NOT EXISTS registries (
+    key            TEXT PRIMARY KEY,
+    contract       TEXT NOT NULL,
+    id             INTEGER NOT NULL,
+    admin          TEXT,
+    created_ledger INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS registry_members (
+    registry TEXT NOT NULL,
+    member   TEXT NOT NULL,
+    ledger   INTEGER,
+    tx_hash  TEXT,
+    added_at TEXT,
+    PRIMARY KEY (registry, member)
+);
+
+CREATE TABLE IF NOT EXISTS admin_accounts (
+    id          TEXT PRIMARY KEY,
+    kind        TEXT NOT NULL,
+    role        TEXT NOT NULL,
+    secret_hash TEXT,
+    created_by  TEXT,
+    created_at  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS admin_audit (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp  TEXT NOT NULL,
+    actor      TEXT,
+    role       TEXT,
+    action     TEXT NOT NULL,
+    target     TEXT,
+    outcome    TEXT NOT NULL,
+    status     INTEGER,
+    details    TEXT,
+    request_id TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_actor ON admin_audit(actor);
+
+CREATE TABLE IF NOT EXISTS tx_log (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    operation TEXT,
+    details   TEXT,
+    status    TEXT,
+    error     TEXT
+);
+`;
+
+// Añade columnas nuevas a tablas creadas por versiones anteriores
+function ensureColumn(db, table, column, type) {
+    const cols = db.prepare(`PRAGMA table_info(${table})`).all();
+    if (!cols.some(c => c.name === column)) {
+        db.exec(`ALTER TABLE ${table} ADD COLUMN ${column} ${type}`);
+    }
+}
+
+// Tablas por pool que guardaban `pool_id INTEGER`, con sus índices (se recrean al reconstruirlas)
+const LEDGER_TABLES = {
+    contributions: ['idx_contributions_pool', 'idx_contributions_contributor', 'idx_contributions_event'],
+    refunds: ['idx_refunds_pool'],
+    vouchers: ['idx_vouchers_member']
+};
+
+const integerPoolId = (db, table) =>
+    db.prepare(`PRAGMA table_info(${table})`).all().find(c => c.name === 'pool_id')?.type.toUpperCase() === 'INTEGER';
+
+// Clave de pool normalizada: "3" (nunca 3 ni "03") o "C...:3"
+function poolKey(poolId) {
+    const s = String(poolId);
+    return /^\d+$/.test(s) ? String(Number(s)) : s;
+}
+
+// Bases anteriores: pools y pool_metadata iban por id numérico (un solo contrato), y
+// contributions/refunds/vouchers guardaban la clave en una columna INTEGER ("3" quedaba como 3).
+// Se reescriben con la clave de pool (lib/pool-keys.js); las filas existentes son del contrato principal.
+function migratePoolKeys(db) {
+    const poolCols = db.prepare('PRAGMA table_info(pools)').all();
+    const rekeyPools = !poolCols.some(c => c.name === 'key');
+    const rekeyMeta = integerPoolId(db, 'pool_metadata');
+    const rekeyLedger = Object.keys(LEDGER_TABLES).filter(t => integerPoolId(db, t));
+    if (!rekeyPools && !rekeyMeta && rekeyLedger.length === 0) return;
+
+    db.transaction(() => {
+        if (rekeyPools) {
+            db.exec('ALTER TABLE pools RENAME TO pools_v1');
+            db.exec(SCHEMA);
+            db.exec(`INSERT INTO pools (key, contract, id, name, creator, supplier, token, goal, raised, deadline, finalized, extra, updated_at)
+                SELECT CAST(id AS TEXT), NULL, id, name, creator, supplier, token, goal, raised, deadline, finalized, extra, updated_at
+                FROM pools_v1`);
+            db.exec('DROP TABLE pools_v1');
+        }
+        if (rekeyMeta) {
+            for (const c of ['xlm_clp_rate REAL', 'rate_source TEXT', 'rate_updated_at TEXT']) {
+                ensureColumn(db, 'pool_metadata', ...c.split(' '));
+            }
+            db.exec('ALTER TABLE pool_metadata RENAME TO pool_metadata_v1');
+            db.exec(SCHEMA);
+            db.exec(`INSERT INTO pool_metadata SELECT CAST(pool_id AS TEXT), category, unit, price_per_unit_clp,
+                target_quantity, delivery_location, description, updated_by, updated_at,
+                xlm_clp_rate, rate_source, rate_updated_at FROM pool_metadata_v1`);
+            db.exec('DROP TABLE pool_metadata_v1');
+        }
+        for (const table of rekeyLedger) {
+            const cols = db.prepare(`PRAGMA table_info(${table})`).all().map(c => c.name);
+            for (const idx of LEDGER_TABLES[table]) db.exec(`DROP INDEX IF EXISTS ${idx}`);
+            db.exec(`ALTER TABLE ${table} RENAME TO ${table}_v1`);
+            db.exec(SCHEMA);
+            const rest = cols.filter(c => c !== 'pool_id').join(', ');
+            db.exec(`INSERT INTO ${table} (pool_id, ${rest}) SELECT CAST(pool_id AS TEXT), ${rest} FROM ${table}_v1`);
+            db.exec(`DROP TABLE ${table}_v1`);
+        }
+    })();
+}
+
+function rowToContribution(row) {
+    return {
+        poolId: String(row.pool_id),
+        contributor: row.contributor,
+        amount: row.amount,
+        ledger: row.ledger,
+        txHash: row.tx_hash,
+        timestamp: row.timestamp
+    };
+}
+
+function rowToMetadata(row) {
+    return {
+        category: row.category,
+        unit: row.unit,
+        pricePerUnitClp: row.price_per_unit_clp,
+        targetQuantity: row.target_quantity,
+        deliveryLocation: row.delivery_location,
+        description: row.description,
+        updatedBy: row.updated_by,
+        updatedAt: row.updated_at,
+        xlmClpRate: row.xlm_clp_rate ?? null,
+        rateSource: row.rate_source ?? null,
+        rateUpdatedAt: row.rate_updated_at ?? null
+    };
+}
+
+function rowToVoucher(row) {
+    return {
+        code: row.code,
+        poolId: String(row.pool_id),
+        contributor: row.contributor,
+        supplier: row.supplier,
+        amount: row.amount,
+        status: row.status,
+        issuedAt: row.issued_at,
+        redeemedAt: row.redeemed_at,
+        redeemedBy: row.redeemed_by
+    };
+}
+
+function rowToSubscription(row) {
+    let kinds = null;
+    try { kinds = row.kinds ? JSON.parse(row.kinds) : null; } catch (_) {}
+    return {
+        address: row.address,
+        email: row.email,
+        webhookUrl: row.webhook_url,
+        kinds,
+        createdAt: row.created_at,
+        updatedAt: row.updated_at
+    };
+}
+
+function rowToPool(row) {
+    let extra = {};
+    try { extra = JSON.parse(row.extra || '{}'); } catch (_) {}
+    return {
+        ...extra,
+        key: row.key,
+        contract: row.contract,
+        id: row.id,
+        name: row.name,
+        creator: row.creator,
+        supplier: row.supplier,
+        token: row.token,
+        goal: row.goal,
+        raised: row.raised,
+        deadline: row.deadline,
+        finalized: Boolean(row.finalized)
+    };
+}
+
+function poolToRow(p) {
+    const extra = {};
+    for (const [k, v] of Object.entries(p)) {
+        if (!POOL_COLUMNS.includes(k)) extra[k] = v;
+    }
+    return {
+        key: String(p.key ?? Number(p.id)),
+        contract: p.contract != null ? String(p.contract) : null,
+        id: Number(p.id),
+        name: String(p.name || ''),
+        creator: p.creator != null ? String(p.creator) : null,
+        supplier: p.supplier != null ? String(p.supplier) : null,
+        token: p.token != null ? String(p.token) : null,
+        goal: String(p.goal ?? '0'),
+        raised: String(p.raised ?? '0'),
+        deadline: Number(p.deadline || 0),
+        finalized: p.finalized ? 1 : 0,
+        extra: JSON.stringify(extra, (_k, v) => typeof v === 'bigint' ? v.toString() : v),
+        updated_at: new Date().toISOString()
+    };
+}
+
+// Abre (o crea) la base y prepara las consultas que usa server.js
+function openStore(file) {
+    fs.mkdirSync(path.dirname(file), { recursive: true });
+    const db = new Database(file);
+    db.pragma('journal_mode = WAL');
+    db.pragma('synchronous = NORMAL');
+    db.exec(SCHEMA);
+    ensureColumn(db, 'contributions', 'event_id', 'TEXT');
+    migratePoolKeys(db);
+    db.exec('CREATE UNIQUE INDEX IF NOT EXISTS idx_contributions_event ON contributions(event_id)');
+
+    const stmt = {
+        allPools: db.prepare('SELECT * FROM pools ORDER BY contract, id'),
+        upsertPool: db.prepare(`
+            INSERT INTO pools (key, contract, id, name, creator, supplier, token, goal, raised, deadline, finalized, extra, updated_at)
+            VALUES (@key, @contract, @id, @name, @creator, @supplier, @token, @goal, @raised, @deadline, @finalized, @extra, @updated_at)
+            ON CONFLICT(key) DO UPDATE SET
+                contract = excluded.contract, name = excluded.name, creator = excluded.creator, supplier = excluded.supplier,
+                token = excluded.token, goal = excluded.goal, raised = excluded.raised,
+                deadline = excluded.deadline, finalized = excluded.finalized,
+                extra = excluded.extra, updated_at = excluded.updated_at`),
+        countPools: db.prepare('SELECT COUNT(*) AS n FROM pools'),
+        allHidden: db.prepare('SELECT id FROM hidden'),
+        addHidden: db.prepare('INSERT OR IGNORE INTO hidden (id) VALUES (?)'),
+        delHidden: db.prepare('DELETE FROM hidden WHERE id = ?'),
+        getMeta: db.prepare('SELECT value FROM meta WHERE key = ?'),
+        setMeta: db.prepare(`INSERT INTO meta (key, value) VALUES (?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value`),
+        insertContribution: db.prepare(`INSERT OR IGNORE INTO contributions
+            (pool_id, contributor, amount, ledger, tx_hash, timestamp, event_id)
+            VALUES (@pool_id, @contributor, @amount, @ledger, @tx_hash, @timestamp, @event_id)`),
+        contributionsByPool: db.prepare('SELECT * FROM contributions WHERE pool_id = ? ORDER BY ledger, id'),
+        contributionsByAccount: db.prepare('SELECT * FROM contributions WHERE contributor = ? ORDER BY ledger, id'),
+        insertRefund: db.prepare(`INSERT OR IGNORE INTO refunds
+            (pool_id, address, amount, ledger, tx_hash, timestamp, event_id)
+            VALUES (@pool_id, @address, @amount, @ledger, @tx_hash, @timestamp, @event_id)`),
+        refundsByPool: db.prepare('SELECT * FROM refunds WHERE pool_id = ? ORDER BY ledger, id'),
+        refundsByPoolAndAccount: db.prepare('SELECT * FROM refunds WHERE pool_id = ? AND address = ? ORDER BY ledger, id'),
+        contributionsByPoolAndAccount: db.prepare('SELECT * FROM contributions WHERE pool_id = ? AND contributor = ? ORDER BY ledger, id'),
+        hasEvent: db.prepare('SELECT 1 FROM events WHERE id = ?'),
+        insertEvent: db.prepare('INSERT OR IGNORE INTO events (id, applied_at) VALUES (?, ?)'),
+        getMetadata: db.prepare('SELECT * FROM pool_metadata WHERE pool_id = ?'),
+        allMetadata: db.prepare('SELECT * FROM pool_metadata'),
+        upsertMetadata: db.prepare(`
+            INSERT INTO pool_metadata (pool_id, category, unit, price_per_unit_clp, target_quantity,
+                                       delivery_location, description, updated_by, updated_at)
+            VALUES (@pool_id, @category, @unit, @price_per_unit_clp, @target_quantity,
+                    @delivery_location, @description, @updated_by, @updated_at)
+            ON CONFLICT(pool_id) DO UPDATE SET
+                category = excluded.category, unit = excluded.unit,
+                price_per_unit_clp = excluded.price_per_unit_clp, target_quantity = excluded.target_quantity,
+                delivery_location = excluded.delivery_location, description = excluded.description,
+                updated_by = excluded.updated_by, updated_at = excluded.updated_at`),
+        insertSession: db.prepare('INSERT OR REPLACE INTO auth_sessions (token_hash, address, expires_at) VALUES (?, ?, ?)'),
+        getSession: db.prepare('SELECT address FROM auth_sessions WHERE token_hash = ? AND expires_at > ?'),
+        deleteSession: db.prepare('DELETE FROM auth_sessions WHERE token_hash = ?'),
+        purgeSessions: db.prepare('DELETE FROM auth_sessions WHERE expires_at <= ?'),
+        insertVoucher: db.prepare(`INSERT INTO vouchers (code, pool_id, contributor, supplier, amount, status, issued_at)
+            VALUES (@code, @pool_id, @contributor, @supplier, @amount, 'issued', @issued_at)`),
+        getVoucher: db.prepare('SELECT * FROM vouchers WHERE code = ?'),
+        vouchersByMember: db.prepare('SELECT * FROM vouchers WHERE pool_id = ? AND contributor = ? ORDER BY issued_at'),
+        vouchersByAccount: db.prepare('SELECT * FROM vouchers WHERE contributor = ? ORDER BY pool_id, issued_at'),
+        redeemVoucher: db.prepare(`UPDATE vouchers SET status = 'redeemed', redeemed_at = ?, redeemed_by = ?
+            WHERE code = ? AND status = 'issued'`),
+        getTracked: db.prepare('SELECT * FROM tx_tracking WHERE hash = ?'),
+        pendingTracked: db.prepare("SELECT * FROM tx_tracking WHERE status = 'PENDING' ORDER BY created_at"),
+        upsertTracked: db.prepare(`
+            INSERT INTO tx_tracking (hash, fn, source, status, ledger, error, callback_status, created_at, updated_at, settled_at)
+            VALUES (@hash, @fn, @source, @status, @ledger, @error, @callback_status, @created_at, @updated_at, @settled_at)
+            ON CONFLICT(hash) DO UPDATE SET
+                fn = COALESCE(excluded.fn, tx_tracking.fn), source = COALESCE(excluded.source, tx_tracking.source),
+                status = excluded.status, ledger = excluded.ledger, error = excluded.error,
+                callback_status = excluded.callback_status, updated_at = excluded.updated_at,
+                settled_at = excluded.settled_at`),
+        // Solo la primera vez: la tasa de creación no se reescribe
+        setCreationRate: db.prepare(`
+            INSERT INTO pool_metadata (pool_id, xlm_clp_rate, rate_source, rate_updated_at, updated_at)
+            VALUES (@pool_id, @rate, @source, @rate_updated_at, @updated_at)
+            ON CONFLICT(pool_id) DO UPDATE SET
+                xlm_clp_rate = excluded.xlm_clp_rate, rate_source = excluded.rate_source,
+                rate_updated_at = excluded.rate_updated_at
+            WHERE pool_metadata.xlm_clp_rate IS NULL`),
+        getSubscription: db.prepare('SELECT * FROM subscriptions WHERE address = ?'),
+        upsertSubscription: db.prepare(`
+            INSERT INTO subscriptions (address, email, webhook_url, kinds, created_at, updated_at)
+            VALUES (@address, @email, @webhook_url, @kinds, @now, @now)
+            ON CONFLICT(address) DO UPDATE SET
+                email = excluded.email, webhook_url = excluded.webhook_url,
+                kinds = excluded.kinds, updated_at = excluded.updated_at`),
+        deleteSubscription: db.prepare('DELETE FROM subscriptions WHERE address = ?'),
+        getNotification: db.prepare('SELECT * FROM notifications WHERE pool_id = ? AND kind = ? AND address = ? AND channel = ?'),
+        upsertNotification: db.prepare(`
+            INSERT INTO notifications (pool_id, kind, address, channel, status, attempts, error, sent_at)
+            VALUES (@pool_id, @kind, @address, @channel, @status, 1, @error, @sent_at)
+            ON CONFLICT(pool_id, kind, address, channel) DO UPDATE SET
+                status = excluded.status, attempts = notifications.attempts + 1,
+                error = excluded.error, sent_at = excluded.sent_at`),
+        notificationsByAddress: db.prepare('SELECT * FROM notifications WHERE address = ? ORDER BY id DESC LIMIT ?'),
+        deletePool: db.prepare('DELETE FROM pools WHERE key = ?'),
+        deleteMetadata: db.prepare('DELETE FROM pool_metadata WHERE pool_id = ?'),
+        upsertRegistry: db.prepare(`INSERT INTO registries (key, contract, id, admin, created_ledger)
+            VALUES (@key, @contract, @id, @admin, @created_ledger)
+            ON CONFLICT(key) DO UPDATE SET admin = excluded.admin`),
+        allRegistries: db.prepare('SELECT * FROM registries ORDER BY contract, id'),
+        insertRegistryMember: db.prepare(`INSERT OR REPLACE INTO registry_members (registry, member, ledger, tx_hash, added_at)
+            VALUES (@registry, @member, @ledger, @tx_hash, @added_at)`),
+        deleteRegistryMember: db.prepare('DELETE FROM registry_members WHERE registry = ? AND member = ?'),
+        registryMembers: db.prepare('SELECT * FROM registry_members WHERE registry = ? ORDER BY ledger, member'),
+        getAdminAccount: db.prepare('SELECT * FROM admin_accounts WHERE id = ?'),
+        listAdminAccounts: db.prepare('SELECT id, kind, role, created_by, created_at FROM admin_accounts ORDER BY created_at'),
+        insertAdminAccount: db.prepare(`INSERT INTO admin_accounts (id, kind, role, secret_hash, created_by, created_at)
+            VALUES (@id, @kind, @role, @secret_hash, @created_by, @created_at)
+            ON CONFLICT(id) DO UPDATE SET kind = excluded.kind, role = excluded.role, secret_hash = excluded.secret_hash`),
+        deleteAdminAccount: db.prepare('DELETE FROM admin_accounts WHERE id = ?'),
+        insertAudit: db.prepare(`INSERT INTO admin_audit (timestamp, actor, role, action, target, outcome, status, details, request_id)
+            VALUES (@timestamp, @actor, @role, @action, @target, @outcome, @status, @details, @request_id)`),
+        listAudit: db.prepare(`SELECT * FROM admin_audit
+            WHERE (@actor IS NULL OR actor = @actor) AND (@action IS NULL OR action = @action)
+            ORDER BY id DESC LIMIT @limit`),
+        insertTx: db.prepare(`INSERT INTO tx_log (timestamp, operation, details, status, error)
+            VALUES (@timestamp, @operation, @details, @status, @error)`),
+        listTx: db.prepare('SELECT * FROM tx_log ORDER BY id DESC LIMIT ?')
+    };
+
+    const store = {
+        db,
+
+        loadPools() {
+            return stmt.allPools.all().map(rowToPool);
+        },
+
+        savePool(p) {
+            stmt.upsertPool.run(poolToRow(p));
+        },
+
+        // Guarda pools, metadatos de escaneo y eventos aplicados en una sola transacción
+        saveSnapshot: db.transaction((poolList, meta = {}, appliedEventIds = []) => {
+            for (const p of poolList) stmt.upsertPool.run(poolToRow(p));
+            for (const [k, v] of Object.entries(meta)) {
+                stmt.setMeta.run(k, v == null ? null : String(v));
+            }
+            const now = new Date().toISOString();
+            for (const id of appliedEventIds) stmt.insertEvent.run(String(id), now);
+        }),
+
+        hasEvent(id) {
+            return Boolean(stmt.hasEvent.get(String(id)));
+        },
+
+        loadHidden() {
+            return stmt.allHidden.all().map(r => r.id);
+        },
+
+        setHidden(id, on) {
+            if (on) stmt.addHidden.run(String(id));
+            else stmt.delHidden.run(String(id));
+        },
+
+        // Devuelve true si la contribución es nueva (mismo event_id = no-op)
+        addContribution(c) {
+            const info = stmt.insertContribution.run({
+                pool_id: poolKey(c.poolId),
+                contributor: String(c.contributor || ''),
+                amount: String(c.amount ?? '0'),
+                ledger: c.ledger ?? null,
+                tx_hash: c.txHash ?? null,
+                timestamp: c.timestamp ?? null,
+                event_id: c.eventId ?? null
+            });
+            return info.changes > 0;
+        },
+
+        contributionsByPool(poolId) {
+            return stmt.contributionsByPool.all(poolKey(poolId)).map(rowToContribution);
+        },
+
+        // Aporte neto por miembro (ctr - rf), en BigInt: los montos i128 no caben en SUM de SQLite
+        memberTotals(poolId) {
+            const totals = new Map();
+            for (const r of stmt.contributionsByPool.all(poolKey(poolId))) {
+                if (!r.contributor) continue;
+                totals.set(r.contributor, (totals.get(r.contributor) ?? 0n) + BigInt(r.amount));
+            }
+            for (const r of stmt.refundsByPool.all(poolKey(poolId))) {
+                if (!r.address) continue;
+                totals.set(r.address, (totals.get(r.address) ?? 0n) - BigInt(r.amount));
+            }
+            return totals;
+        },
+
+        contributionsByAccount(address) {
+            return stmt.contributionsByAccount.all(String(address)).map(rowToContribution);
+        },
+
+        addRefund(r) {
+            const info = stmt.insertRefund.run({
+                pool_id: poolKey(r.poolId),
+                address: String(r.address || ''),
+                amount: String(r.amount ?? '0'),
+                ledger: r.ledger ?? null,
+                tx_hash: r.txHash ?? null,
+                timestamp: r.timestamp ?? null,
+                event_id: r.eventId ?? null
+            });
+            return info.changes > 0;
+        },
+
+        // Aportado y reembolsado por una cuenta en una pool (BigInt)
+        memberBalance(poolId, address) {
+            const sum = rows => rows.reduce((acc, r) => acc + BigInt(r.amount), 0n);
+            return {
+                contributed: sum(stmt.contributionsByPoolAndAccount.all(poolKey(poolId), String(address))),
+                refunded: sum(stmt.refundsByPoolAndAccount.all(poolKey(poolId), String(address)))
+            };
+        },
+
+        getMetadata(poolId) {
+            const row = stmt.getMetadata.get(String(poolId));
+            return row ? rowToMetadata(row) : null;
+        },
+
+        // pool_id -> metadatos, para adjuntar en /api/pools
+        allMetadata() {
+            const out = new Map();
+            for (const row of stmt.allMetadata.all()) out.set(String(row.pool_id), rowToMetadata(row));
+            return out;
+        },
+
+        // Aplica un parche (ya validado) sobre los metadatos existentes
+        saveMetadata(poolId, patch, updatedBy) {
+            const cur = store.getMetadata(poolId) || {};
+            const next = { ...cur, ...patch };
+            stmt.upsertMetadata.run({
+                pool_id: String(poolId),
+                category: next.category ?? null,
+                unit: next.unit ?? null,
+                price_per_unit_clp: next.pricePerUnitClp ?? null,
+                target_quantity: next.targetQuantity ?? null,
+                delivery_location: next.deliveryLocation ?? null,
+                description: next.description ?? null,
+                updated_by: updatedBy ?? null,
+                updated_at: new Date().toISOString()
+            });
+            return store.getMetadata(poolId);
+        },
+
+        // Sesiones de auth: solo se guarda el hash del token
+        saveSession(tokenHash, address, expiresAt) {
+            stmt.purgeSessions.run(Date.now());
+            stmt.insertSession.run(tokenHash, address, expiresAt);
+        },
+
+        getSessionAddress(tokenHash, now = Date.now()) {
+            const row = stmt.getSession.get(tokenHash, now);
+            return row ? row.address : null;
+        },
+
+        deleteSession(tokenHash) {
+            stmt.deleteSession.run(tokenHash);
+        },
+
+        setCreationRate(poolId, { rate, source, updatedAt }) {
+            return stmt.setCreationRate.run({
+                pool_id: String(poolId),
+                rate: Number(rate),
+                source: source ?? null,
+                rate_updated_at: updatedAt ?? null,
+                updated_at: new Date().toISOString()
+            }).changes === 1;
+        },
+
+        getMeta(key) {
+            const row = stmt.getMeta.get(key);
+            return row ? row.value : null;
+        },
+
+        setMeta(key, value) {
+            stmt.setMeta.run(key, value == null ? null : String(value));
+        },
+
+        // Vouchers: el tope por aportante se valida dentro de la misma transacción que inserta
+        issueVoucher(v, capacity) {
+            return db.transaction(() => {
+                const issued = stmt.vouchersByMember.all(poolKey(v.poolId), String(v.contributor))
+                    .reduce((acc, r) => acc + BigInt(r.amount), 0n);
+                if (issued + BigInt(v.amount) > BigInt(capacity)) return null;
+                stmt.insertVoucher.run({
+                    code: v.code,
+                    pool_id: poolKey(v.poolId),
+                    contributor: String(v.contributor),
+                    supplier: String(v.supplier),
+                    amount: String(v.amount),
+                    issued_at: new Date().toISOString()
+                });
+                return rowToVoucher(stmt.getVoucher.get(v.code));
+            })();
+        },
+
+        getVoucher(code) {
+            const row = stmt.getVoucher.get(String(code));
+            return row ? rowToVoucher(row) : null;
+        },
+
+        vouchersByMember(poolId, address) {
+            return stmt.vouchersByMember.all(poolKey(poolId), String(address)).map(rowToVoucher);
+        },
+
+        vouchersByAccount(address) {
+            return stmt.vouchersByAccount.all(String(address)).map(rowToVoucher);
+        },
+
+        // Canje de un solo uso: false si ya estaba canjeado
+        redeemVoucher(code, by) {
+            return stmt.redeemVoucher.run(new Date().toISOString(), String(by), String(code)).changes === 1;
+        },
+
+        // Transacciones enviadas que el servidor sigue hasta que se asientan
+        getTracked(hash) {
+            return stmt.getTracked.get(hash) || null;
+        },
+
+        pendingTracked() {
+            return stmt.pendingTracked.all();
+        },
+
+        saveTracked(row) {
+            stmt.upsertTracked.run({
+                hash: row.hash,
+                fn: row.fn ?? null,
+                source: row.source ?? null,
+                status: row.status,
+                ledger: row.ledger ?? null,
+                error: row.error ?? null,
+                callback_status: row.callback_status ?? null,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                settled_at: row.settled_at ?? null
+            });
+        },
+
+        // Suscripciones a notificaciones (una por dirección)
+        getSubscription(address) {
+            const row = stmt.getSubscription.get(String(address));
+            return row ? rowToSubscription(row) : null;
+        },
+
+        saveSubscription(address, { email = null, webhookUrl = null, kinds = null } = {}) {
+            stmt.upsertSubscription.run({
+                address: String(address),
+                email,
+                webhook_url: webhookUrl,
+                kinds: kinds ? JSON.stringify(kinds) : null,
+                now: new Date().toISOString()
+            });
+            return store.getSubscription(address);
+        },
+
+        deleteSubscription(address) {
+            return stmt.deleteSubscription.run(String(address)).changes === 1;
+        },
+
+        // Una fila por (pool, tipo, destinatario, canal): evita repetir avisos entre ticks y reinicios
+        getNotification(poolId, kind, address, channel) {
+            return stmt.getNotification.get(String(poolId), kind, String(address), channel) || null;
+        },
+
+        recordNotification(n) {
+            stmt.upsertNotification.run({
+                pool_id: String(n.poolId),
+                kind: n.kind,
+                address: String(n.address),
+                channel: n.channel,
+                status: n.status,
+                error: n.error != null ? String(n.error) : null,
+                sent_at: new Date().toISOString()
+            });
+        },
+
+        notificationsByAddress(address, limit = 50) {
+            return stmt.notificationsByAddress.all(String(address), limit).map(r => ({
+                poolId: r.pool_id,
+                kind: r.kind,
+                channel: r.channel,
+                status: r.status,
+                attempts: r.attempts,
+                error: r.error,
+                sentAt: r.sent_at
+            }));
+        },
+
+        // Purga una pool (registro falso): fila, metadatos y marca de oculta; el ledger de eventos no se toca
+        deletePool: db.transaction((key) => {
+            const n = stmt.deletePool.run(String(key)).changes;
+            stmt.deleteMetadata.run(String(key));
+            stmt.delHidden.run(String(key));
+            return n === 1;
+        }),
+
+        // Registros de miembros (eventos rg/ma/mr), por clave (contrato, id) igual que las pools
+        saveRegistry(r) {
+            stmt.upsertRegistry.run({
+                key: String(r.key),
+                contract: r.contract,
+                id: Number(r.id),
+                admin: r.admin ?? null,
+                created_ledger: r.ledger ?? null
+            });
+        },
+
+        loadRegistries() {
+            return stmt.allRegistries.all().map(r => ({
+                ...r,
+                members: stmt.registryMembers.all(r.key).map(m => m.member)
+            }));
+        },
+
+        addRegistryMember(m) {
+            stmt.insertRegistryMember.run({
+                registry: String(m.registry),
+                member: m.member,
+                ledger: m.ledger ?? null,
+                tx_hash: m.txHash ?? null,
+                added_at: m.timestamp ?? new Date().toISOString()
+            });
+        },
+
+        removeRegistryMember(registry, member) {
+            stmt.deleteRegistryMember.run(String(registry), member);
+        },
+
+        registryMembers(registry) {
+            return stmt.registryMembers.all(String(registry));
+        },
+
+        // Cuentas admin: dirección Stellar (sesión firmada) o credencial con secreto hasheado
+        getAdminAccount(id) {
+            return stmt.getAdminAccount.get(String(id)) || null;
+        },
+
+        listAdminAccounts() {
+            return stmt.listAdminAccounts.all();
+        },
+
+        saveAdminAccount(a) {
+            stmt.insertAdminAccount.run({
+                id: String(a.id),
+                kind: a.kind,
+                role: a.role,
+                secret_hash: a.secretHash ?? null,
+                created_by: a.createdBy ?? null,
+                created_at: new Date().toISOString()
+            });
+        },
+
+        deleteAdminAccount(id) {
+            return stmt.deleteAdminAccount.run(String(id)).changes === 1;
+        },
+
+        appendAudit(e) {
+            stmt.insertAudit.run({
+                timestamp: new Date().toISOString(),
+                actor: e.actor ?? null,
+                role: e.role ?? null,
+                action: e.action,
+                target: e.target != null ? String(e.target) : null,
+                outcome: e.outcome,
+                status: e.status ?? null,
+                details: e.details != null ? JSON.stringify(e.details) : null,
+                request_id: e.requestId ?? null
+            });
+        },
+
+        listAudit({ actor = null, action = null, limit = 200 } = {}) {
+            return stmt.listAudit.all({ actor, action, limit: Math.min(Number(limit) || 200, 1000) }).map(r => ({
+                ...r,
+                details: r.details ? JSON.parse(r.details) : null
+            }));
+        },
+
+        appendTx(entry) {
+            stmt.insertTx.run({
+                timestamp: entry.timestamp || new Date().toISOString(),
+                operation: entry.operation ?? null,
+                details: entry.details != null ? JSON.stringify(entry.details) : null,
+                status: entry.status ?? null,
+                error: entry.error != null ? String(entry.error) : null
+            });
+        },
+
+        listTx(limit = 100) {
+            return stmt.listTx.all(limit).map(r => ({
+                ...r,
+                details: r.details ? JSON.parse(r.details) : null
+            }));
+        },
+
+        close() {
+            try { db.close(); } catch (_) {}
+        }
+    };
+
+    store.migrateFromJson = db.transaction((stateFile, txLogFile) => {
+        // Migración única desde los JSON antiguos; los archivos quedan intactos
+        if (store.getMeta('json_migrated')) return { pools: 0, tx: 0 };
+        let nPools = 0, nTx = 0;
+
+        if (stateFile && fs.existsSync(stateFile) && stmt.countPools.get().n === 0) {
+            const data = JSON.parse(fs.readFileSync(stateFile, 'utf8'));
+            for (const p of data.pools || []) {
+                if (p == null || p.id == null) continue;
+                stmt.upsertPool.run(poolToRow(p));
+                nPools++;
+            }
+            for (const id of data.hidden || []) stmt.addHidden.run(String(id));
+            stmt.setMeta.run('lastScannedLedger', String(Math.max(0, Number(data.lastScannedLedger || 0))));
+        }
+
+        if (txLogFile && fs.existsSync(txLogFile)) {
+            const arr = JSON.parse(fs.readFileSync(txLogFile, 'utf8'));
+            for (const entry of Array.isArray(arr) ? arr : []) {
+                store.appendTx(entry);
+                nTx++;
+            }
+        }
+
+        stmt.setMeta.run('json_migrated', new Date().toISOString());
+        return { pools: nPools, tx: nTx };
+    });
+
+    return store;
+}
+
+module.exports = { openStore };
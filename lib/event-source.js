@@ -0,0 +1,81 @@
+const fs = require('fs');
+const path = require('path');
+const StellarSdk = require('@stellar/stellar-sdk');
+
+// Una fuente de eventos expone lo mismo que usa el indexador del SorobanRpc.Server:
+//   getLatestLedger() -> { sequence }
+//   getEvents({ startLedger | cursor, filters, limit }) -> { latestLedger, events }
+
+// Fuente en vivo: RPC de Soroban
+function createRpcEventSource(rpcUrl) {
+    const server = new StellarSdk.SorobanRpc.Server(rpcUrl, { allowHttp: true });
+    return {
+        kind: 'rpc',
+        server,
+        getLatestLedger: () => server.getLatestLedger(),
+        getEvents: (request) => server.getEvents(request)
+    };
+}
+
+// Lee páginas grabadas de getEvents. Acepta un archivo o un directorio de .json;
+// cada archivo es una respuesta cruda ({ latestLedger, events }) o { pages: [...] }.
+function readFixturePages(fixturePath) {
+    const files = fs.statSync(fixturePath).isDirectory()
+        ? fs.readdirSync(fixturePath).filter(f => f.endsWith('.json')).sort().map(f => path.join(fixturePath, f))
+        : [fixturePath];
+
+    const pages = [];
+    for (const file of files) {
+        const data = JSON.parse(fs.readFileSync(file, 'utf8'));
+        if (Array.isArray(data)) pages.push(...data);
+        else if (Array.isArray(data.pages)) pages.push(...data.pages);
+        else pages.push(data);
+    }
+    return pages;
+}
+
+// Fuente offline: reproduce eventos grabados respetando startLedger, cursor y limit
+// como lo haría el RPC, para poder probar el indexador sin red.
+function createFixtureEventSource(fixturePath) {
+    const pages = readFixturePages(fixturePath);
+    const events = pages
+        .flatMap(p => p.events || [])
+        .sort((a, b) => String(a.pagingToken ?? a.id).localeCompare(String(b.pagingToken ?? b.id)));
+    const latestLedger = Math.max(
+        0,
+        ...pages.map(p => Number(p.latestLedger || 0)),
+        ...events.map(e => Number(e.ledger || 0))
+    );
+
+    return {
+        kind: 'fixture',
+        getLatestLedger: async () => ({ sequence: latestLedger }),
+        getEvents: async ({ startLedger, cursor, limit = 100, filters = [] } = {}) => {
+            const ids = filters.flatMap(f => f.contractIds || []);
+            let from = 0;
+            if (cursor) {
+                from = events.findIndex(e => (e.pagingToken ?? e.id) === cursor);
+                if (from < 0) throw new Error(`cursor not found: ${cursor}`);
+                from += 1;
+            }
+            const out = events
+                .slice(from)
+                .filter(e => cursor || Number(e.ledger) >= Number(startLedger || 0))
+                .filter(e => ids.length === 0 || !e.contractId || ids.includes(String(e.contractId)))
+                .slice(0, limit);
+            // Mismo formato que devuelve SorobanRpc.Server#getEvents
+            return StellarSdk.SorobanRpc.parseRawEvents({ latestLedger, events: out });
+        }
+    };
+}
+
+// Elige la fuente según el entorno: EVENT_SOURCE=fixture + EVENT_FIXTURES=<ruta>
+function createEventSource({ kind, rpcUrl, fixtures } = {}) {
+    if (kind === 'fixture') {
+        if (!fixtures) throw new Error('EVENT_FIXTURES es obligatorio con EVENT_SOURCE=fixture');
+        return createFixtureEventSource(fixtures);
+    }
+    return createRpcEventSource(rpcUrl);
+}
+
+module.exports = { createEventSource, createRpcEventSource, createFixtureEventSource };
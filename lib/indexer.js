@@ -0,0 +1,580 @@
+const { scValToNative, xdr } = require('@stellar/stellar-sdk');
+const { createPoolKeys } = require('./pool-keys');
+const { normalizeMilestones, normalizeLimits } = require('./chain');
+
+const DEFAULT_WINDOW = 150_000; // ajusta si quieres más ventana
+// Eventos por página de getEvents
+const PAGE_LIMIT = 200;
+
+// Helpers para parsing robusto de eventos
+function scvFromAny(x) {
+    try {
+        if (xdr.ScVal.isValid(x)) return x;
+    } catch(_) {}
+    try { return typeof x === 'string' ? xdr.ScVal.fromXDR(x, 'base64') : null; } catch(_) { return null; }
+}
+
+function scvToNativeSafe(scvLike) {
+    try {
+        const scv = scvFromAny(scvLike) || scvLike;
+        return scValToNative(scv);
+    } catch(_) { return null; }
+}
+
+function topicSym(t) {
+    const v = scvToNativeSafe(t);
+    // Símbolos en Soroban suelen decodificar a string
+    return typeof v === 'string' ? v : (v && v.sym) ? v.sym : String(v);
+}
+
+function topicNum(t) {
+    const v = scvToNativeSafe(t);
+    const n = Number(v);
+    return Number.isFinite(n) ? n : null;
+}
+
+function extractAmount(payload) {
+    try {
+        if (typeof payload === 'bigint') return payload;
+        if (typeof payload === 'number') return BigInt(payload);
+        if (typeof payload === 'string' && /^-?\d+$/.test(payload)) return BigInt(payload);
+        if (payload && typeof payload === 'object') {
+            for (const k of ['amount','delta','value','raised','contribution']) {
+                if (payload[k] != null) return BigInt(payload[k]);
+            }
+            if (Array.isArray(payload)) {
+                for (const it of payload) {
+                    const v = extractAmount(it);
+                    if (v !== 0n) return v;
+                }
+            }
+        }
+    } catch(_) {}
+    return 0n;
+}
+
+// Dirección del aportante/reembolsado: tópico 2 del evento (ctr, rf) o campo del payload
+function extractContributor(native, topics) {
+    if (Array.isArray(topics) && topics[2]) {
+        const v = scvToNativeSafe(topics[2]);
+        if (typeof v === 'string' && v) return v;
+    }
+    if (native && typeof native === 'object') {
+        for (const k of ['from','contributor','user','member']) {
+            if (typeof native[k] === 'string') return native[k];
+        }
+        if (Array.isArray(native) && typeof native[0] === 'string') return native[0];
+    }
+    return null;
+}
+
+function extractPoolId(native, topics) {
+    // 1) payload con id
+    if (native && typeof native === 'object') {
+        for (const k of ['id','pool','pool_id','poolId']) {
+            const n = Number(native[k]);
+            if (Number.isFinite(n) && n > 0) return n;
+        }
+    }
+    // 2) tópico 1 suele ser el id
+    if (Array.isArray(topics) && topics[1]) {
+        const n = topicNum(topics[1]);
+        if (Number.isFinite(n) && n > 0) return n;
+    }
+    // 3) búsqueda en todos los tópicos
+    if (Array.isArray(topics)) {
+        for (const t of topics) {
+            const n = topicNum(t);
+            if (Number.isFinite(n) && n > 0) return n;
+        }
+    }
+    return null;
+}
+
+// Contrato emisor: string crudo (fixtures) o Contract (parseRawEvents)
+function eventContract(e) {
+    const c = e.contractId;
+    if (!c) return null;
+    return typeof c === 'string' ? c : (typeof c.contractId === 'function' ? c.contractId() : String(c));
+}
+
+// Pool financiada con hitos pendientes cuyo siguiente plazo ya venció: lo no liberado se reembolsa
+function isLapsed(p, now) {
+    if (p.finalized || BigInt(p.raised) < BigInt(p.goal)) return false;
+    const next = (p.milestones || [])[Number(p.next_milestone || 0)];
+    return Boolean(next) && now > Number(next.deadline);
+}
+
+// Cuánto puede reclamar todavía un miembro con `refund` (mismas reglas que el contrato).
+// `balance`: { contributed, refunded } del ledger de aportes, en BigInt.
+function refundableFor(p, { contributed, refunded }, now) {
+    const raised = BigInt(p.raised);
+    // Cancelada o con hito vencido: reembolsable sin esperar el vencimiento ni mirar la meta
+    const open = isLapsed(p, now) || Boolean(p.cancelled);
+    let reason = null;
+    if (p.finalized) reason = 'finalized';
+    else if (!open && now <= Number(p.deadline)) reason = 'not_expired';
+    else if (!open && raised >= BigInt(p.goal)) reason = 'goal_reached';
+    if (reason) return { refundable: 0n, reason };
+
+    // Pool con hitos ya financiado: a prorrata de lo no liberado (`raised` queda fijo en el contrato)
+    const proRata = (p.milestones || []).length > 0 && raised >= BigInt(p.goal);
+    const owed = proRata ? contributed * (raised - BigInt(p.released ?? 0)) / raised : contributed;
+    const pending = owed - refunded;
+    if (pending <= 0n) return { refundable: 0n, reason: contributed > 0n ? 'already_refunded' : 'no_contribution' };
+    return { refundable: pending, reason: null };
+}
+
+// Estado calculado de una pool (now en segundos)
+function poolStatus(p, now) {
+    if (p.finalized) return 'finalized';
+    if (p.cancelled) return 'cancelled';
+    if (isLapsed(p, now)) return 'lapsed';
+    if (Number(p.next_milestone || 0) > 0) return 'delivering';
+    if (now > Number(p.deadline)) return 'expired';
+    return BigInt(p.raised) >= BigInt(p.goal) ? 'funded' : 'active';
+}
+
+// Pools en las que todavía se puede hacer algo (aportar, reembolsar o finalizar)
+function isActionable(p, now) {
+    const raised = BigInt(p.raised);
+    const goal   = BigInt(p.goal);
+    const expired = now > Number(p.deadline);
+    const funded  = raised >= goal;
+    // 0) cancelada: mientras quede algo por reembolsar
+    if (p.cancelled) return !p.finalized && raised - BigInt(p.released ?? 0) - BigInt(p.refunded ?? 0) > 0n;
+    // 1) activa
+    if (!p.finalized && !expired) return true;
+    // 2) vencida y reembolsable
+    if (!p.finalized && expired && raised < goal) return true;
+    // 3) financiada pero no finalizada (incluye hitos pendientes o vencidos)
+    if (!p.finalized && funded) return true;
+    return false;
+}
+
+// Indexador de eventos de uno o más contratos de pools: reconstruye pools desde una fuente
+// de eventos (RPC en vivo o fixtures). `store` y `logger` son opcionales: sin store todo queda en memoria.
+// `onEvent(change)` se llama por cada evento nuevo aplicado durante hydrate.
+// El Map `pools` va por clave (contrato, id): ver lib/pool-keys.js.
+function createIndexer({ source, store = null, contractId, contractIds = null, logger = null, onEvent = null }) {
+    const keys = createPoolKeys(contractIds || [contractId]);
+    const pools = new Map();
+    // Buffer para contribuciones huérfanas (cuando la pool no existe aún)
+    const pendingRaised = new Map(); // clave de pool -> BigInt
+    const state = {
+        lastScannedLedger: 0,
+        eventCursor: null // pagingToken del último evento leído: se reanuda exactamente ahí
+    };
+    // Eventos aplicados en este proceso (el store cubre los de arranques anteriores)
+    const seenEvents = new Set();
+    // Aportantes por pool cuando no hay store que los recuerde
+    const contributors = new Map();
+
+    // Aportantes distintos de una pool: lo mismo que cuenta `members` en el contrato
+    function countMembers(key, contributor = null) {
+        if (store?.memberTotals) return store.memberTotals(key).size;
+        if (!contributors.has(key)) contributors.set(key, new Set());
+        if (contributor) contributors.get(key).add(contributor);
+        return contributors.get(key).size;
+    }
+    // Registros de miembros por clave (contrato, id): { id, contract, key, admin, members: Set }
+    const registries = new Map();
+    // Candado para evitar resyncs solapados
+    let hydratingPromise = null;
+
+    // Vuelca pools, cursor y eventos aplicados en una sola transacción
+    function save(appliedEventIds = []) {
+        if (!store) return;
+        try {
+            store.saveSnapshot([...pools.values()], {
+                lastScannedLedger: state.lastScannedLedger,
+                eventCursor: state.eventCursor,
+                pendingRaised: JSON.stringify(Object.fromEntries([...pendingRaised].map(([k, v]) => [k, v.toString()])))
+            }, appliedEventIds);
+        } catch (e) {
+            logger?.error('error persistiendo estado', { operation: 'INDEXER', error: String(e) });
+        }
+    }
+
+    function load() {
+        if (!store) return;
+        pools.clear();
+        for (const p of store.loadPools()) {
+            const contract = p.contract || keys.primary;
+            pools.set(keys.key(contract, p.id), { ...p, contract, key: keys.key(contract, p.id) });
+        }
+        state.lastScannedLedger = Math.max(0, Number(store.getMeta('lastScannedLedger') || 0));
+        state.eventCursor = store.getMeta('eventCursor') || null;
+        registries.clear();
+        for (const r of store.loadRegistries?.() || []) {
+            registries.set(r.key, { id: Number(r.id), contract: r.contract, key: r.key, admin: r.admin, members: new Set(r.members) });
+        }
+        pendingRaised.clear();
+        const pend = JSON.parse(store.getMeta('pendingRaised') || '{}');
+        for (const [k, v] of Object.entries(pend)) pendingRaised.set(k, BigInt(v));
+    }
+
+    // Aplica un evento al estado en memoria; devuelve un resumen del cambio (o false)
+    function applyEvent(e) {
+        const raw = e.value?.xdr || e.value || e.data?.xdr || e.data;
+        const native = scvToNativeSafe(raw);
+        const topics = e.topics || e.topic || [];
+
+        const tag = topics[0] ? topicSym(topics[0]) : null;
+        if (['rg', 'ma', 'mr'].includes(tag)) return applyRegistryEvent(e, tag, native, topics);
+        const pid = extractPoolId(native, topics);
+        if (!pid) return false; // sin id no podemos aplicar
+
+        const contract = eventContract(e) || keys.primary;
+        if (!keys.contracts.includes(contract)) return false;
+        const key = keys.key(contract, pid);
+        const tagNorm = (tag || '').toLowerCase();
+        let type = 'other';
+        let amount = null;
+        let milestone = null;
+        let deadline = null;
+
+        // PC / PoolCreated
+        if (tagNorm === 'pc' || /pool.*created|created|create_pool/i.test(tagNorm)) {
+            const prev = pools.get(key);
+            const obj = {
+                ...(prev || {}),  // 👈 conserva prev.name si existía
+                ...((typeof native === 'object' && native) || {}),
+                id: pid,
+                contract,
+                key,
+                goal: String((native?.goal ?? native?.target ?? 0)),
+                raised: String((native?.raised ?? 0)),
+                deadline: Number(native?.deadline ?? 0),
+                finalized: Boolean(native?.finalized),
+                milestones: normalizeMilestones(native?.milestones),
+                next_milestone: Number(native?.next_milestone ?? 0),
+                released: String(native?.released ?? 0),
+                refunded: String(native?.refunded ?? 0),
+                limits: normalizeLimits(native?.limits),
+                members: countMembers(key),
+                registry: native?.registry == null ? null : Number(native.registry),
+                cancelled: Boolean(native?.cancelled),
+                extension_quorum_bps: Number(native?.extension_quorum_bps ?? 0),
+                extensions: Number(native?.extensions ?? 0),
+                extension: prev?.extension ?? null,
+            };
+
+            // Aplicar contribuciones huérfanas si las hay
+            const buff = pendingRaised.get(key);
+            if (typeof buff === 'bigint' && buff !== 0n) {
+                obj.raised = (BigInt(obj.raised ?? '0') + buff).toString();
+                pendingRaised.delete(key);
+                // Aplicando contribuciones huérfanas
+            }
+
+            pools.set(key, obj);
+            type = 'pc';
+            // Pool creada
+        }
+        // CTR / Contributed
+        else if (tagNorm === 'ctr' || /contribut|contribute/i.test(tagNorm)) {
+            const p = pools.get(key);
+            const delta = extractAmount(native);  // soporta struct/tupla/string
+            type = 'ctr';
+            amount = delta.toString();
+            const contributor = extractContributor(native, topics);
+
+            // Ledger por aportante (quién, cuánto, cuándo, en qué tx)
+            try {
+                store?.addContribution({
+                    poolId: key,
+                    contributor,
+                    amount: delta.toString(),
+                    ledger: e.ledger ?? e.ledgerSequence ?? null,
+                    txHash: e.txHash ?? null,
+                    timestamp: e.ledgerClosedAt ?? null,
+                    eventId: e.id ?? e.pagingToken ?? null
+                });
+            } catch (_) {}
+            const members = countMembers(key, contributor);
+            if (p) {
+                const prev = BigInt(p.raised ?? '0');
+                p.raised = (prev + delta).toString();
+                p.members = members;
+                // Contribución aplicada
+            } else {
+                // Contribución huérfana: la pool no existe aún, la guardamos para después
+                const cur = pendingRaised.get(key) ?? 0n;
+                pendingRaised.set(key, cur + delta);
+                // Contribución huérfana
+            }
+        }
+        // MS / hito liberado al proveedor: ("ms", id, index) -> monto
+        else if (tagNorm === 'ms' || /milestone/i.test(tagNorm)) {
+            const p = pools.get(key);
+            const delta = extractAmount(native);
+            type = 'ms';
+            amount = delta.toString();
+            milestone = Array.isArray(topics) && topics[2] ? topicNum(topics[2]) : null;
+            if (p) {
+                p.released = (BigInt(p.released ?? '0') + delta).toString();
+                p.next_milestone = milestone != null ? milestone + 1 : Number(p.next_milestone || 0) + 1;
+            }
+        }
+        // RF / Refund
+        else if (tagNorm === 'rf' || /refund/i.test(tagNorm)) {
+            const p = pools.get(key);
+            const delta = extractAmount(native);
+            type = 'rf';
+            amount = delta.toString();
+            if (p && (p.milestones || []).length && BigInt(p.raised ?? '0') >= BigInt(p.goal ?? '0')) {
+                // Hito vencido: el contrato deja `raised` fijo y acumula lo devuelto aparte
+                p.refunded = (BigInt(p.refunded ?? '0') + delta).toString();
+            } else if (p) {
+                const next = BigInt(p.raised ?? '0') - delta;
+                p.raised = (next > 0n ? next : 0n).toString();
+            } else {
+                const cur = pendingRaised.get(key) ?? 0n;
+                pendingRaised.set(key, cur - delta);
+            }
+            try {
+                store?.addRefund({
+                    poolId: key,
+                    address: extractContributor(native, topics),
+                    amount: delta.toString(),
+                    ledger: e.ledger ?? e.ledgerSequence ?? null,
+                    txHash: e.txHash ?? null,
+                    timestamp: e.ledgerClosedAt ?? null,
+                    eventId: e.id ?? e.pagingToken ?? null
+                });
+            } catch (_) {}
+        }
+        // CN / cancelada por el creador: reembolsable de inmediato (dato: lo que queda por devolver)
+        else if (tagNorm === 'cn' || /cancel/i.test(tagNorm)) {
+            const p = pools.get(key);
+            type = 'cn';
+            amount = extractAmount(native).toString();
+            if (p) {
+                p.cancelled = true;
+                // Momento de la cancelación: desde ahí corre el lookback de los avisos
+                const at = Date.parse(e.ledgerClosedAt ?? '');
+                p.cancelled_at = Number.isFinite(at) ? Math.floor(at / 1000) : null;
+            }
+        }
+        // XP / extensión de plazo propuesta: ("xp", id) -> nueva fecha
+        else if (tagNorm === 'xp') {
+            const p = pools.get(key);
+            type = 'xp';
+            deadline = Number(extractAmount(native));
+            if (p) {
+                p.extension = { deadline, votes: '0' };
+                p.extensions = Number(p.extensions || 0) + 1;
+            }
+        }
+        // XV / voto a favor: ("xv", id, votante) -> peso (su aporte)
+        else if (tagNorm === 'xv') {
+            const p = pools.get(key);
+            type = 'xv';
+            amount = extractAmount(native).toString();
+            if (p?.extension) p.extension.votes = (BigInt(p.extension.votes) + BigInt(amount)).toString();
+        }
+        // XT / mayoría alcanzada: ("xt", id) -> plazo nuevo
+        else if (tagNorm === 'xt') {
+            const p = pools.get(key);
+            type = 'xt';
+            deadline = Number(extractAmount(native));
+            if (p) {
+                p.deadline = deadline;
+                p.extension = null;
+            }
+        }
+        // FN / Finalized
+        else if (tagNorm === 'fn' || /finaliz/i.test(tagNorm)) {
+            const p = pools.get(key);
+            if (p) {
+                p.finalized = true;
+                // Sin hitos, fn trae todo lo pagado; con hitos, el total liberado
+                p.released = extractAmount(native).toString();
+            }
+            type = 'fn';
+        } else if (pid) {
+            // ⚙️ Fallback genérico: si veo un id pero no reconozco tag,
+            // creo/actualizo un contenedor con campos mínimos
+            const prev = pools.get(key) || { id: pid, contract, key, goal: "0", raised: "0", deadline: 0, finalized: false };
+            const p = { ...prev };
+            // si el payload trae algo útil, copiarlo
+            if (native && typeof native === 'object') {
+                if (native.goal     != null) p.goal     = String(native.goal);
+                if (native.raised   != null) p.raised   = String(native.raised);
+                if (native.deadline != null) p.deadline = Number(native.deadline);
+                if (native.finalized!= null) p.finalized= Boolean(native.finalized);
+            }
+            pools.set(key, p);
+        }
+        const pool = pools.get(key);
+        return {
+            type,
+            poolId: pid,
+            contract,
+            key,
+            ledger: e.ledger ?? e.ledgerSequence ?? null,
+            txHash: e.txHash ?? null,
+            ...(type === 'ctr' || type === 'rf' ? { address: extractContributor(native, topics), amount } : {}),
+            ...(type === 'ms' ? { milestone, amount } : {}),
+            ...(type === 'cn' ? { amount } : {}),
+            ...(type === 'xv' ? { address: extractContributor(native, topics), amount } : {}),
+            ...(type === 'xp' || type === 'xt' ? { deadline } : {}),
+            pool: pool ? { ...pool } : null
+        };
+    }
+
+    // RG / registro creado: ("rg", id) -> admin; MA/MR: ("ma"|"mr", id, miembro) -> total de miembros
+    function applyRegistryEvent(e, tag, native, topics) {
+        const rid = topicNum(topics[1]);
+        const contract = eventContract(e) || keys.primary;
+        if (!rid || !keys.contracts.includes(contract)) return false;
+        const key = keys.key(contract, rid);
+        const ledger = e.ledger ?? e.ledgerSequence ?? null;
+        if (!registries.has(key)) registries.set(key, { id: rid, contract, key, admin: null, members: new Set() });
+        const r = registries.get(key);
+        let member = null;
+
+        if (tag === 'rg') {
+            r.admin = typeof native === 'string' ? native : null;
+            try { store?.saveRegistry({ ...r, ledger }); } catch (_) {}
+        } else {
+            member = extractContributor(native, topics);
+            if (!member) return false;
+            try {
+                store?.saveRegistry({ ...r, ledger });
+                if (tag === 'ma') {
+                    store?.addRegistryMember({ registry: key, member, ledger, txHash: e.txHash ?? null, timestamp: e.ledgerClosedAt ?? null });
+                } else {
+                    store?.removeRegistryMember(key, member);
+                }
+            } catch (_) {}
+            if (tag === 'ma') r.members.add(member);
+            else r.members.delete(member);
+        }
+        // Sin `key`/`poolId`: el frontend refresca pools con esos campos
+        return {
+            type: tag,
+            registryId: rid,
+            registryKey: key,
+            contract,
+            ledger,
+            txHash: e.txHash ?? null,
+            ...(member ? { member } : {}),
+            registry: { id: rid, contract, key, admin: r.admin, members: r.members.size }
+        };
+    }
+
+    // Reconstruye estado desde eventos
+    async function hydrate(fromLedger) {
+        if (hydratingPromise) return hydratingPromise;
+        hydratingPromise = (async () => {
+            try {
+            const latest = await source.getLatestLedger();
+
+            // Nunca uses 0. Para "full resync" arranca en 1 y deja que el RPC te diga el mínimo real.
+            const clampPos = (x) => Math.max(1, Number(x || 0));
+            let start = (fromLedger === 0)
+              ? 1
+              : clampPos(fromLedger || state.lastScannedLedger || (latest.sequence - DEFAULT_WINDOW));
+
+            // Sin ledger explícito se reanuda desde el cursor persistido (evento exacto)
+            let paginationToken = (fromLedger == null && state.eventCursor) ? state.eventCursor : undefined;
+            let cursorFailed = false;
+
+            retryFetch:
+            do {
+                let ev;
+                try {
+                  ev = await source.getEvents({
+                    ...(paginationToken ? { cursor: paginationToken } : { startLedger: start }),
+                    filters: [{ type: 'contract', contractIds: keys.contracts }],
+                    limit: PAGE_LIMIT
+                  });
+                } catch (e) {
+                  const msg = String(e?.message || e);
+                  // 0) Cursor inválido o fuera de retención -> volver a startLedger
+                  if (paginationToken && !cursorFailed) {
+                    cursorFailed = true;
+                    paginationToken = undefined;
+                    continue retryFetch;
+                  }
+                  // 1) startLedger <= 0
+                  if (/must be positive/i.test(msg)) {
+                    start = clampPos(latest.sequence - DEFAULT_WINDOW);
+                    // Ajustando startLedger
+                    paginationToken = undefined;
+                    continue retryFetch;
+                  }
+                  // 2) Fuera del rango permitido -> extrae mínimo/máximo del error
+                  const m = /within the ledger range:\s*(\d+)\s*-\s*(\d+)/i.exec(msg);
+                  if (m) {
+                    const min = Number(m[1]), max = Number(m[2]);
+                    if (start < min) {
+                      // Ajuste startLedger
+                      start = min; paginationToken = undefined; continue retryFetch;
+                    }
+                    if (start > max) {
+                      const newStart = Math.max(min, max - DEFAULT_WINDOW);
+                      // Ajuste startLedger
+                      start = newStart; paginationToken = undefined; continue retryFetch;
+                    }
+                  }
+                  // Otro error: registra y sal
+                  logger?.warn('getEvents falló', { operation: 'INDEXER', start, error: msg });
+                  break;
+                }
+
+                const events = ev.events || [];
+                const appliedIds = [];
+                for (const e of events) {
+                    const eventId = e.id ?? e.pagingToken;
+                    paginationToken = e.pagingToken ?? e.id ?? paginationToken;
+                    state.eventCursor = paginationToken;
+                    // Asegura avanzar el puntero de ledger con nombres alternativos
+                    state.lastScannedLedger = Math.max(
+                        state.lastScannedLedger,
+                        e.ledger ?? e.ledgerSequence ?? 0
+                    );
+
+                    // Idempotencia: un evento ya aplicado (resync, reinicio) no se vuelve a sumar
+                    if (eventId && (seenEvents.has(eventId) || store?.hasEvent(eventId))) continue;
+                    if (eventId) { seenEvents.add(eventId); appliedIds.push(eventId); }
+
+                    const change = applyEvent(e);
+                    if (change && onEvent) {
+                        try { onEvent(change); } catch (_) {}
+                    }
+                }
+
+                // 🔸 Estado, cursor y eventos aplicados se guardan juntos por página
+                if (events.length > 0) save(appliedIds);
+                if (events.length < PAGE_LIMIT) break;
+            } while (paginationToken);
+
+            // Hidratación completada
+            } catch (e) {
+                logger?.error('error hidratando', { operation: 'INDEXER', error: String(e?.message || e) });
+            } finally {
+                hydratingPromise = null;
+            }
+        })();
+        return hydratingPromise;
+    }
+
+    return { pools, registries, keys, pendingRaised, state, applyEvent, hydrate, save, load };
+}
+
+module.exports = {
+    PAGE_LIMIT,
+    scvToNativeSafe,
+    extractAmount,
+    extractContributor,
+    extractPoolId,
+    eventContract,
+    isLapsed,
+    refundableFor,
+    poolStatus,
+    isActionable,
+    createIndexer
+};
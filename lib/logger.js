@@ -0,0 +1,161 @@
+const fs = require('fs');
+const path = require('path');
+const crypto = require('crypto');
+
+const LEVELS = { debug: 10, info: 20, warn: 30, error: 40 };
+const FILE_RE = /^agrocoop-(\d{4}-\d{2}-\d{2})(?:\.(\d+))?\.log$/;
+
+function normalizeLevel(level, fallback = 'info') {
+    const l = String(level || '').toLowerCase();
+    return LEVELS[l] ? l : fallback;
+}
+
+function toMillis(v) {
+    if (v == null || v === '') return null;
+    const n = Number(v);
+    if (Number.isFinite(n)) return n;
+    const t = Date.parse(String(v));
+    return Number.isFinite(t) ? t : null;
+}
+
+// Logger JSON por líneas con rotación diaria y por tamaño en `dir`
+function createLogger({
+    dir,
+    level = 'info',
+    maxBytes = 5 * 1024 * 1024,
+    retentionDays = 14,
+    mirrorToConsole = false
+} = {}) {
+    const minLevel = LEVELS[normalizeLevel(level)];
+    let day = null;
+    let file = null;
+    let size = 0;
+
+    function purgeOld() {
+        const cutoff = Date.now() - retentionDays * 86_400_000;
+        for (const f of fs.readdirSync(dir)) {
+            const m = FILE_RE.exec(f);
+            if (m && Date.parse(m[1]) < cutoff) {
+                try { fs.unlinkSync(path.join(dir, f)); } catch (_) {}
+            }
+        }
+    }
+
+    function currentFile(bytes) {
+        const today = new Date().toISOString().slice(0, 10);
+        if (today !== day) {
+            fs.mkdirSync(dir, { recursive: true });
+            day = today;
+            file = path.join(dir, `agrocoop-${day}.log`);
+            size = fs.existsSync(file) ? fs.statSync(file).size : 0;
+            purgeOld();
+        }
+        if (size > 0 && size + bytes > maxBytes) {
+            // Rota: agrocoop-DIA.log -> agrocoop-DIA.N.log (N = siguiente libre)
+            let n = 1;
+            while (fs.existsSync(path.join(dir, `agrocoop-${day}.${n}.log`))) n++;
+            fs.renameSync(file, path.join(dir, `agrocoop-${day}.${n}.log`));
+            size = 0;
+        }
+        return file;
+    }
+
+    function write(lvl, msg, fields = {}) {
+        const l = normalizeLevel(lvl);
+        if (LEVELS[l] < minLevel) return;
+        const entry = { ts: new Date().toISOString(), level: l, msg: String(msg ?? ''), ...fields };
+        const line = JSON.stringify(entry, (_k, v) => typeof v === 'bigint' ? v.toString() : v) + '\n';
+        try {
+            const bytes = Buffer.byteLength(line);
+            fs.appendFileSync(currentFile(bytes), line);
+            size += bytes;
+        } catch (_) {
+            // Sin disco no hay log; nunca botar el proceso por esto
+        }
+        if (mirrorToConsole) (l === 'error' ? console.error : console.log)(line.trimEnd());
+    }
+
+    function bind(bound) {
+        return {
+            log: (lvl, msg, fields) => write(lvl, msg, { ...bound, ...fields }),
+            debug: (msg, fields) => write('debug', msg, { ...bound, ...fields }),
+            info: (msg, fields) => write('info', msg, { ...bound, ...fields }),
+            warn: (msg, fields) => write('warn', msg, { ...bound, ...fields }),
+            error: (msg, fields) => write('error', msg, { ...bound, ...fields }),
+            child: (more) => bind({ ...bound, ...more })
+        };
+    }
+
+    // Busca entradas (más recientes primero). `level` es la severidad mínima.
+    function query({ level: minLvl, operation, requestId, from, to, limit = 200 } = {}) {
+        const fromMs = toMillis(from);
+        const toMs = toMillis(to);
+        const min = minLvl ? LEVELS[normalizeLevel(minLvl, 'debug')] : 0;
+        const op = operation ? String(operation).toUpperCase() : null;
+        const max = Math.max(1, Math.min(1000, Number(limit) || 200));
+
+        let files = [];
+        try { files = fs.readdirSync(dir).filter(f => FILE_RE.test(f)); } catch (_) { return []; }
+        // Más nuevos primero: por día y luego el archivo activo antes que los rotados (.N mayor = más nuevo)
+        files.sort((a, b) => {
+            const [, da, na] = FILE_RE.exec(a);
+            const [, db, nb] = FILE_RE.exec(b);
+            if (da !== db) return da < db ? 1 : -1;
+            const ra = na ? Number(na) : Infinity;
+            const rb = nb ? Number(nb) : Infinity;
+            return rb - ra;
+        });
+
+        const out = [];
+        for (const f of files) {
+            const fileDay = Date.parse(FILE_RE.exec(f)[1]);
+            if (fromMs != null && fileDay + 86_400_000 <= fromMs) continue;
+            if (toMs != null && fileDay > toMs) continue;
+
+            let lines = [];
+            try { lines = fs.readFileSync(path.join(dir, f), 'utf8').split('\n'); } catch (_) { continue; }
+            for (let i = lines.length - 1; i >= 0; i--) {
+                if (!lines[i]) continue;
+                let e;
+                try { e = JSON.parse(lines[i]); } catch (_) { continue; }
+                const ts = Date.parse(e.ts);
+                if (fromMs != null && ts < fromMs) continue;
+                if (toMs != null && ts > toMs) continue;
+                if (min && (LEVELS[e.level] || 0) < min) continue;
+                if (op && String(e.operation || '').toUpperCase() !== op) continue;
+                if (requestId && e.requestId !== requestId && e.relatedRequestId !== requestId) continue;
+                out.push(e);
+                if (out.length >= max) return out;
+            }
+        }
+        return out;
+    }
+
+    return { ...bind({}), query };
+}
+
+// Asigna un id a cada request (respeta X-Request-Id entrante) y registra su resultado
+function requestLogger(logger) {
+    return (req, res, next) => {
+        const incoming = String(req.headers['x-request-id'] || '').trim();
+        req.id = /^[\w.-]{1,64}$/.test(incoming) ? incoming : crypto.randomUUID();
+        req.log = logger.child({ requestId: req.id });
+        res.setHeader('X-Request-Id', req.id);
+
+        const started = process.hrtime.bigint();
+        res.on('finish', () => {
+            const durationMs = Number(process.hrtime.bigint() - started) / 1e6;
+            // Solo path: el query string puede traer datos sensibles
+            const level = res.statusCode >= 500 ? 'error' : res.statusCode >= 400 ? 'warn' : 'info';
+            req.log.log(level, 'request', {
+                method: req.method,
+                path: req.path,
+                status: res.statusCode,
+                durationMs: Math.round(durationMs * 10) / 10
+            });
+        });
+        next();
+    };
+}
+
+module.exports = { LEVELS, normalizeLevel, createLogger, requestLogger };
@@ -0,0 +1,59 @@
+// Metadatos off-chain de una pool (producto, precio, entrega). Nunca los toca el indexador.
+
+// Categorías: las mismas claves que PREFABS en public/index.html, más "otro"
+const CATEGORIES = ['lenia', 'gas', 'canasta', 'fertilizante', 'semillas', 'herramientas', 'medicinas', 'electricidad', 'otro'];
+const UNITS = ['unidad', 'kg', 'saco', 'litro', 'm3', 'caja', 'balon', 'kwh', 'servicio'];
+
+const TEXT_LIMITS = { deliveryLocation: 200, description: 2000 };
+
+// Valida y normaliza un parche de metadatos. Campos ausentes no se tocan;
+// null o '' borra el campo.
+function normalizeMetadata(input) {
+    const value = {};
+    const errors = [];
+    if (!input || typeof input !== 'object' || Array.isArray(input)) {
+        return { value, errors: ['metadata must be an object'] };
+    }
+
+    const has = (k) => Object.prototype.hasOwnProperty.call(input, k);
+    const empty = (v) => v === null || v === '';
+
+    if (has('category')) {
+        const c = empty(input.category) ? null : String(input.category).toLowerCase().trim();
+        if (c !== null && !CATEGORIES.includes(c)) errors.push(`category must be one of ${CATEGORIES.join(', ')}`);
+        else value.category = c;
+    }
+
+    if (has('unit')) {
+        const u = empty(input.unit) ? null : String(input.unit).toLowerCase().trim();
+        if (u !== null && !UNITS.includes(u)) errors.push(`unit must be one of ${UNITS.join(', ')}`);
+        else value.unit = u;
+    }
+
+    // Precio en CLP (entero, sin decimales)
+    if (has('pricePerUnitClp')) {
+        const v = input.pricePerUnitClp;
+        if (empty(v)) value.pricePerUnitClp = null;
+        else if (!/^\d+$/.test(String(v)) || BigInt(v) <= 0n) errors.push('pricePerUnitClp must be a positive integer');
+        else value.pricePerUnitClp = String(BigInt(v));
+    }
+
+    if (has('targetQuantity')) {
+        const v = input.targetQuantity;
+        const n = Number(v);
+        if (empty(v)) value.targetQuantity = null;
+        else if (!Number.isFinite(n) || n <= 0) errors.push('targetQuantity must be a positive number');
+        else value.targetQuantity = n;
+    }
+
+    for (const [k, max] of Object.entries(TEXT_LIMITS)) {
+        if (!has(k)) continue;
+        const t = empty(input[k]) ? null : String(input[k]).trim();
+        if (t !== null && t.length > max) errors.push(`${k} must be at most ${max} characters`);
+        else value[k] = t || null;
+    }
+
+    return { value, errors };
+}
+
+module.exports = { CATEGORIES, UNITS, normalizeMetadata };
@@ -0,0 +1,65 @@
+const fs = require('fs');
+const { Asset } = require('@stellar/stellar-sdk');
+
+// "native" | "CODIGO:EMISOR" (SAC de un activo clásico) | "C..." -> id del contrato del token
+function tokenContractId(token, { networkPassphrase, nativeTokenId }) {
+    if (token === 'native') return nativeTokenId;
+    const i = token.indexOf(':');
+    if (i < 0) return token;
+    return new Asset(token.slice(0, i), token.slice(i + 1)).contractId(networkPassphrase);
+}
+
+// Perfil de red (testnet, futurenet, mainnet, local) desde config/networks.json.
+// Variables de entorno sueltas siguen pisando campos: CONTRACT_ID, TOKEN_ID, SOROBAN_RPC, NETWORK_PASSPHRASE.
+// `contractId` es el contrato donde se crean pools; `extraContracts` (o EXTRA_CONTRACT_IDS, separados
+// por coma) son versiones anteriores que se siguen indexando en paralelo.
+// `poolTerms: false` marca un contrato desplegado antes de `PoolTerms`: create_pool va sin terms.
+// Un CONTRACT_ID propio se asume redesplegado con el build actual; POOL_TERMS=0/1 lo fuerza.
+// `tokens` (o TOKEN_IDS) son los tokens ofrecidos al crear una pool: "native", "CODIGO:EMISOR" o "C...".
+function loadNetworkProfile({ file, name, env = process.env } = {}) {
+    const data = JSON.parse(fs.readFileSync(file, 'utf8'));
+    const network = name || data.default || 'testnet';
+    const base = data.networks?.[network];
+    if (!base) {
+        throw new Error(`red desconocida "${network}" (disponibles: ${Object.keys(data.networks || {}).join(', ')})`);
+    }
+
+    const profile = {
+        network,
+        networkPassphrase: env.NETWORK_PASSPHRASE || base.networkPassphrase,
+        rpcUrl: env.SOROBAN_RPC || base.rpcUrl,
+        horizonUrl: env.HORIZON_URL || base.horizonUrl || null,
+        friendbotUrl: base.friendbotUrl || null,
+        contractId: env.CONTRACT_ID || base.contractId,
+        tokenId: env.TOKEN_ID || base.tokenId || 'native',
+        poolTerms: env.POOL_TERMS != null
+            ? env.POOL_TERMS === '1'
+            : Boolean(env.CONTRACT_ID) || base.poolTerms !== false
+    };
+
+    const missing = [
+        ['networkPassphrase', 'NETWORK_PASSPHRASE'],
+        ['rpcUrl', 'SOROBAN_RPC'],
+        ['contractId', 'CONTRACT_ID']
+    ].filter(([k]) => !profile[k]);
+    if (missing.length) {
+        throw new Error(`perfil "${network}" incompleto: define ${missing.map(([k, e]) => `${k} (o ${e})`).join(', ')}`);
+    }
+
+    const extra = env.EXTRA_CONTRACT_IDS != null
+        ? String(env.EXTRA_CONTRACT_IDS).split(',').map(s => s.trim()).filter(Boolean)
+        : (base.extraContracts || []);
+    profile.contractIds = [...new Set([profile.contractId, ...extra])];
+
+    // "native": el SAC de XLM de esa red (se deriva del passphrase)
+    profile.nativeTokenId = Asset.native().contractId(profile.networkPassphrase);
+    if (profile.tokenId === 'native') profile.tokenId = profile.nativeTokenId;
+
+    const tokens = env.TOKEN_IDS != null
+        ? String(env.TOKEN_IDS).split(',').map(s => s.trim()).filter(Boolean)
+        : (base.tokens || []);
+    profile.tokenIds = [...new Set([profile.tokenId, ...tokens.map(t => tokenContractId(t, profile))])];
+    return profile;
+}
+
+module.exports = { loadNetworkProfile };
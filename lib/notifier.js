@@ -0,0 +1,285 @@
+const fs = require('fs');
+const path = require('path');
+const { poolStatus, refundableFor } = require('./indexer');
+
+// Avisos a miembros suscritos: "quedan 24 h", "meta alcanzada" y "reembolso disponible".
+// El scheduler corre en el servidor (no depende de que alguien tenga la pestaña abierta)
+// y se despierta también con cada evento que aplica el indexador.
+
+const KINDS = ['deadline_soon', 'goal_reached', 'refund_available'];
+
+class NotifyError extends Error {
+    constructor(message, status = 400) {
+        super(message);
+        this.status = status;
+    }
+}
+
+// i128 en unidades -> "12.5" con los decimales del token
+function formatUnits(value, decimals = 7) {
+    const v = BigInt(value ?? 0);
+    const base = 10n ** BigInt(decimals);
+    const frac = (v % base).toString().padStart(decimals, '0').replace(/0+$/, '');
+    return frac ? `${v / base}.${frac}` : (v / base).toString();
+}
+
+// Qué avisos corresponden a una pool en este momento (level-triggered: el registro evita repetirlos)
+function dueKinds(pool, now, remindBeforeSec) {
+    // Finalizada: ya no hay nada que avisar (ni meta alcanzada ni reembolsos)
+    if (pool.finalized) return [];
+    const status = poolStatus(pool, now);
+    // Cancelada por el creador: reembolso inmediato
+    if (status === 'cancelled') return ['refund_available'];
+    // Hito vencido sin confirmar: lo no liberado ya se puede reembolsar
+    if (status === 'lapsed') return ['refund_available'];
+    if (BigInt(pool.goal) > 0n && BigInt(pool.raised) >= BigInt(pool.goal)) return ['goal_reached'];
+    if (status === 'expired') return ['refund_available'];
+    if (status === 'active' && Number(pool.deadline) - now <= remindBeforeSec) return ['deadline_soon'];
+    return [];
+}
+
+// Desde cuándo rige el estado actual: el lookback se mide desde ahí y no desde el vencimiento
+// de la pool (los hitos vencen siempre después)
+function changedAt(pool, now) {
+    if (pool.cancelled && pool.cancelled_at != null) return Number(pool.cancelled_at);
+    if (poolStatus(pool, now) === 'lapsed') return Number(pool.milestones[Number(pool.next_milestone || 0)].deadline);
+    return Number(pool.deadline);
+}
+
+// --- canales ---
+// Cada canal: { name, accepts(subscription), send(subscription, message) }
+
+function createConsoleChannel({ out = console } = {}) {
+    return {
+        name: 'console',
+        accepts: () => true,
+        async send(sub, msg) {
+            out.log(`🔔 [${msg.kind}] ${sub.address}: ${msg.title} — ${msg.text}`);
+        }
+    };
+}
+
+// Una línea JSON por aviso; útil para desarrollo local
+function createFileChannel(file) {
+    return {
+        name: 'file',
+        accepts: () => true,
+        async send(sub, msg) {
+            fs.mkdirSync(path.dirname(file), { recursive: true });
+            fs.appendFileSync(file, JSON.stringify({ to: sub.address, ...msg }) + '\n');
+        }
+    };
+}
+
+// POST JSON a la URL de la suscripción o, si no tiene, a la URL global (NOTIFY_WEBHOOK_URL)
+function createWebhookChannel({ url = null, fetchImpl = globalThis.fetch, timeoutMs = 5000 } = {}) {
+    return {
+        name: 'webhook',
+        accepts: sub => Boolean(sub.webhookUrl || url),
+        async send(sub, msg) {
+            const r = await fetchImpl(sub.webhookUrl || url, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ to: sub.address, ...msg }),
+                signal: AbortSignal.timeout(timeoutMs)
+            });
+            if (!r.ok) throw new Error(`webhook HTTP ${r.status}`);
+        }
+    };
+}
+
+// SMTP vía nodemailer (solo se carga si el canal está configurado)
+function createSmtpChannel({ host, port = 587, secure = false, user = null, pass = null, from, transport = null }) {
+    if (!transport && !host) throw new Error('SMTP_HOST es obligatorio con el canal smtp');
+    const t = transport || require('nodemailer').createTransport({
+        host, port: Number(port), secure: Boolean(secure), auth: user ? { user, pass } : undefined
+    });
+    return {
+        name: 'smtp',
+        accepts: sub => Boolean(sub.email),
+        async send(sub, msg) {
+            await t.sendMail({ from: from || user, to: sub.email, subject: msg.title, text: msg.text });
+        }
+    };
+}
+
+// NOTIFY_CHANNELS=console,file,webhook,smtp
+function createChannels(names, env = process.env, { dataDir = 'data' } = {}) {
+    return String(names || '').split(',').map(s => s.trim()).filter(Boolean).map(name => {
+        if (name === 'console') return createConsoleChannel();
+        if (name === 'file') return createFileChannel(env.NOTIFY_FILE || path.join(dataDir, 'notifications.log'));
+        if (name === 'webhook') return createWebhookChannel({ url: env.NOTIFY_WEBHOOK_URL || null });
+        if (name === 'smtp') {
+            return createSmtpChannel({
+                host: env.SMTP_HOST,
+                port: env.SMTP_PORT || 587,
+                secure: env.SMTP_SECURE === '1',
+                user: env.SMTP_USER || null,
+                pass: env.SMTP_PASS || null,
+                from: env.SMTP_FROM
+            });
+        }
+        throw new Error(`canal de notificación desconocido: ${name}`);
+    });
+}
+
+// Valida lo que manda el miembro al suscribirse
+function normalizeSubscription(body = {}) {
+    const email = body.email ? String(body.email).trim() : null;
+    const webhookUrl = body.webhookUrl ? String(body.webhookUrl).trim() : null;
+    if (email && !/^[^\s@]+@[^\s@]+\.[^\s@]+$/.test(email)) throw new NotifyError('invalid email');
+    if (webhookUrl && !/^https?:\/\//i.test(webhookUrl)) throw new NotifyError('webhookUrl must be http(s)');
+    let kinds = null;
+    if (body.kinds != null) {
+        if (!Array.isArray(body.kinds) || body.kinds.some(k => !KINDS.includes(k))) {
+            throw new NotifyError(`kinds must be a subset of ${KINDS.join(', ')}`);
+        }
+        kinds = [...new Set(body.kinds)];
+    }
+    return { email, webhookUrl, kinds };
+}
+
+function createNotifier({
+    pools,
+    store,
+    channels,
+    tokens = null,
+    logger = null,
+    remindBeforeSec = 24 * 3600,
+    lookbackSec = 7 * 24 * 3600, // estados de hace más que esto ya no avisan (p. ej. al primer arranque)
+    maxAttempts = 5,
+    intervalMs = 60_000
+}) {
+    let timer = null;
+    let running = null;
+
+    // Creador (para finalizar) y aportantes; el reembolso solo a quien tiene aporte neto
+    function recipients(pool, kind) {
+        const out = new Set();
+        for (const [address, net] of store.memberTotals(pool.key ?? pool.id)) {
+            if (net > 0n) out.add(address);
+        }
+        if (kind !== 'refund_available' && pool.creator) out.add(String(pool.creator));
+        return [...out];
+    }
+
+    // `address`/`now` solo importan en refund_available: el monto es el de refundableFor para ese miembro
+    async function message(pool, kind, address, now) {
+        const t = tokens ? await tokens.resolve(pool.token) : { decimals: 7, symbol: 'XLM' };
+        const sym = t.symbol || 'tokens';
+        const name = pool.name || `Pool #${pool.id}`;
+        const raised = `${formatUnits(pool.raised, t.decimals)} ${sym}`;
+        const goal = `${formatUnits(pool.goal, t.decimals)} ${sym}`;
+        const deadline = new Date(Number(pool.deadline) * 1000).toISOString();
+        const refundable = kind === 'refund_available'
+            ? refundableFor(pool, store.memberBalance(pool.key ?? pool.id, address), now).refundable
+            : null;
+        const refund = `${formatUnits(refundable ?? 0n, t.decimals)} ${sym}`;
+        const partial = BigInt(pool.released ?? 0) > 0n;
+        const text = {
+            deadline_soon: `La cooperativa ${name} vence el ${deadline}. Lleva ${raised} de ${goal}.`,
+            goal_reached: `La cooperativa ${name} llegó a la meta (${raised} de ${goal}). El creador ya puede finalizar y pagar al proveedor.`,
+            refund_available: pool.cancelled
+                ? `La cooperativa ${name} fue cancelada por su creador. Ya puedes pedir el reembolso de ${refund}${partial ? ' (tu parte de lo no liberado)' : ''}.`
+                : BigInt(pool.raised) >= BigInt(pool.goal)
+                ? `Un hito de entrega de la cooperativa ${name} venció sin confirmarse. Ya puedes pedir el reembolso de ${refund} (tu parte de lo no liberado).`
+                : `La cooperativa ${name} venció sin llegar a la meta (${raised} de ${goal}). Ya puedes pedir el reembolso de ${refund}.`
+        }[kind];
+        const title = {
+            deadline_soon: `Quedan menos de ${Math.round(remindBeforeSec / 3600)} h: ${name}`,
+            goal_reached: `Meta alcanzada: ${name}`,
+            refund_available: `Reembolso disponible: ${name}`
+        }[kind];
+        return {
+            kind,
+            poolId: Number(pool.id),
+            contract: pool.contract ?? null,
+            key: pool.key ?? String(pool.id),
+            title,
+            text,
+            raised: String(pool.raised),
+            goal: String(pool.goal),
+            deadline: Number(pool.deadline),
+            token: pool.token ?? null,
+            ...(refundable != null ? { refundable: refundable.toString() } : {})
+        };
+    }
+
+    async function checkPool(pool, now) {
+        let sent = 0;
+        const key = pool.key ?? String(pool.id);
+        for (const kind of dueKinds(pool, now, remindBeforeSec)) {
+            for (const address of recipients(pool, kind)) {
+                let msg = null;
+                const sub = store.getSubscription(address);
+                if (!sub || (sub.kinds && !sub.kinds.includes(kind))) continue;
+                for (const ch of channels) {
+                    if (!ch.accepts(sub)) continue;
+                    const prev = store.getNotification(key, kind, address, ch.name);
+                    if (prev && (prev.status === 'sent' || prev.attempts >= maxAttempts)) continue;
+                    msg = msg || await message(pool, kind, address, now);
+                    try {
+                        await ch.send(sub, msg);
+                        store.recordNotification({ poolId: key, kind, address, channel: ch.name, status: 'sent' });
+                        sent++;
+                    } catch (e) {
+                        store.recordNotification({ poolId: key, kind, address, channel: ch.name, status: 'failed', error: e.message || e });
+                        logger?.warn('aviso no entregado', { operation: 'NOTIFY', poolId: key, kind, channel: ch.name, error: String(e.message || e) });
+                    }
+                }
+            }
+        }
+        return sent;
+    }
+
+    // Revisa todas las pools; nunca corre dos veces en paralelo
+    function tick(now = Math.floor(Date.now() / 1000)) {
+        if (running) return running;
+        running = (async () => {
+            let sent = 0;
+            for (const pool of [...pools.values()]) {
+                if (!pool || !pool.creator || pool.goal == null || pool.raised == null) continue;
+                if (now - changedAt(pool, now) > lookbackSec) continue;
+                try { sent += await checkPool(pool, now); } catch (e) {
+                    logger?.error('error revisando avisos', { operation: 'NOTIFY', poolId: pool.key ?? pool.id, error: String(e.message || e) });
+                }
+            }
+            if (sent) logger?.info('avisos enviados', { operation: 'NOTIFY', sent });
+            return sent;
+        })().finally(() => { running = null; });
+        return running;
+    }
+
+    // Callback para el indexador: ctr/rf/fn pueden cambiar lo que corresponde avisar
+    function onPoolEvent() {
+        setImmediate(() => { tick().catch(() => {}); });
+    }
+
+    function start() {
+        if (!timer && channels.length) {
+            timer = setInterval(() => { tick().catch(() => {}); }, intervalMs);
+            tick().catch(() => {});
+        }
+    }
+
+    function stop() {
+        if (timer) clearInterval(timer);
+        timer = null;
+    }
+
+    return { tick, checkPool, onPoolEvent, start, stop };
+}
+
+module.exports = {
+    KINDS,
+    NotifyError,
+    formatUnits,
+    dueKinds,
+    normalizeSubscription,
+    createConsoleChannel,
+    createFileChannel,
+    createWebhookChannel,
+    createSmtpChannel,
+    createChannels,
+    createNotifier
+};
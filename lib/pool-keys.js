@@ -0,0 +1,27 @@
+// Una pool se identifica por (contrato, id). Las del contrato principal conservan
+// la clave corta "3" (URLs, base y ocultas de antes siguen valiendo); las de otros
+// contratos indexados usan "C...:3".
+function createPoolKeys(contractIds) {
+    const contracts = [...new Set((contractIds || []).filter(Boolean).map(String))];
+    if (contracts.length === 0) throw new Error('at least one contract id is required');
+    const primary = contracts[0];
+
+    function key(contract, id) {
+        const c = contract ? String(contract) : primary;
+        return c === primary ? String(Number(id)) : `${c}:${Number(id)}`;
+    }
+
+    // "3" | "C...:3" -> { contract, id, key }; null si no es válida o el contrato no se indexa
+    function parse(input, contract = null) {
+        const s = String(input ?? '').trim();
+        const i = s.lastIndexOf(':');
+        const c = i >= 0 ? s.slice(0, i) : (contract ? String(contract) : primary);
+        const id = Number(i >= 0 ? s.slice(i + 1) : s);
+        if (!Number.isInteger(id) || id <= 0 || !contracts.includes(c)) return null;
+        return { contract: c, id, key: key(c, id) };
+    }
+
+    return { primary, contracts, key, parse };
+}
+
+module.exports = { createPoolKeys };
@@ -0,0 +1,114 @@
+const fs = require('fs');
+
+// Precio XLM/CLP con fuente intercambiable:
+//   http: feed JSON configurable (PRICE_FEED_URL + PRICE_FEED_PATH, p. ej. CoinGecko)
+//   file: JSON local { "rate": 250.5, "updatedAt": "..." } para operar sin red
+// Sobre cualquier fuente, un admin puede fijar un override manual (persistido en meta).
+
+const DEFAULT_FEED_URL = 'https://api.coingecko.com/api/v3/simple/price?ids=stellar&vs_currencies=clp&include_last_updated_at=true';
+const DEFAULT_FEED_PATH = 'stellar.clp';
+
+function pick(obj, dotted) {
+    return String(dotted || '').split('.').filter(Boolean).reduce((o, k) => (o == null ? o : o[k]), obj);
+}
+
+function positiveRate(v) {
+    const n = Number(v);
+    return Number.isFinite(n) && n > 0 ? n : null;
+}
+
+function createHttpPriceSource({ url = DEFAULT_FEED_URL, path = DEFAULT_FEED_PATH, fetchImpl = globalThis.fetch, timeoutMs = 5000 } = {}) {
+    return {
+        kind: 'http',
+        async fetch() {
+            const r = await fetchImpl(url, { signal: AbortSignal.timeout(timeoutMs) });
+            if (!r.ok) throw new Error(`price feed HTTP ${r.status}`);
+            const body = await r.json();
+            const rate = positiveRate(pick(body, path));
+            if (rate == null) throw new Error(`price feed has no positive value at "${path}"`);
+            // CoinGecko trae last_updated_at (segundos) junto al precio
+            const ts = pick(body, path.split('.').slice(0, -1).concat('last_updated_at').join('.'));
+            return { rate, updatedAt: ts ? new Date(Number(ts) * 1000).toISOString() : new Date().toISOString() };
+        }
+    };
+}
+
+function createFilePriceSource(file) {
+    return {
+        kind: 'file',
+        async fetch() {
+            const data = JSON.parse(fs.readFileSync(file, 'utf8'));
+            const rate = positiveRate(data.rate ?? data.xlmClp);
+            if (rate == null) throw new Error(`price file ${file} has no positive "rate"`);
+            return { rate, updatedAt: data.updatedAt || fs.statSync(file).mtime.toISOString() };
+        }
+    };
+}
+
+function createPriceSource({ kind, url, path, file } = {}) {
+    if (kind === 'file') {
+        if (!file) throw new Error('PRICE_FILE es obligatorio con PRICE_SOURCE=file');
+        return createFilePriceSource(file);
+    }
+    return createHttpPriceSource({ url: url || DEFAULT_FEED_URL, path: path || DEFAULT_FEED_PATH });
+}
+
+// Cache con TTL; si la fuente falla se sirve el último valor (stale) o, sin nada, el fallback
+function createPriceOracle({ source, store = null, ttlMs = 5 * 60_000, fallbackRate = 100, logger = null }) {
+    let cached = null; // { rate, updatedAt, fetchedAt }
+    let inflight = null;
+
+    function override() {
+        const raw = store?.getMeta('priceOverride');
+        if (!raw) return null;
+        try { return JSON.parse(raw); } catch (_) { return null; }
+    }
+
+    async function refresh() {
+        if (!inflight) {
+            inflight = source.fetch()
+                .then(v => { cached = { ...v, fetchedAt: Date.now() }; return cached; })
+                .finally(() => { inflight = null; });
+        }
+        return inflight;
+    }
+
+    async function get() {
+        const o = override();
+        if (o) return { pair: 'XLM/CLP', rate: o.rate, source: 'override', updatedAt: o.updatedAt, setBy: o.setBy ?? null, stale: false };
+
+        if (!cached || Date.now() - cached.fetchedAt > ttlMs) {
+            try {
+                await refresh();
+            } catch (e) {
+                logger?.warn('fuente de precio falló', { operation: 'PRICE', source: source.kind, error: String(e.message || e) });
+                if (!cached) {
+                    return { pair: 'XLM/CLP', rate: fallbackRate, source: 'fallback', updatedAt: null, stale: true };
+                }
+                return { pair: 'XLM/CLP', rate: cached.rate, source: source.kind, updatedAt: cached.updatedAt, stale: true };
+            }
+        }
+        return { pair: 'XLM/CLP', rate: cached.rate, source: source.kind, updatedAt: cached.updatedAt, stale: false };
+    }
+
+    async function setOverride(rate, setBy = null) {
+        const r = positiveRate(rate);
+        if (r == null) throw new Error('rate must be a positive number');
+        store.setMeta('priceOverride', JSON.stringify({ rate: r, updatedAt: new Date().toISOString(), setBy }));
+        return get();
+    }
+
+    async function clearOverride() {
+        store.setMeta('priceOverride', null);
+        return get();
+    }
+
+    return { get, refresh, setOverride, clearOverride };
+}
+
+module.exports = {
+    createHttpPriceSource,
+    createFilePriceSource,
+    createPriceSource,
+    createPriceOracle
+};
@@ -0,0 +1,90 @@
+const { poolStatus } = require('./indexer');
+
+// Server-Sent Events: empuja a los dashboards los eventos que aplica el indexador
+// (pc, ctr, ms, rf, cn, xp, xv, xt, fn), los de registros de miembros (rg, ma, mr) y los cambios de estado
+// calculado (active/funded/delivering/lapsed/expired/cancelled/finalized).
+function createPoolStream({ pools, logger = null, bufferSize = 200, heartbeatMs = 25_000 } = {}) {
+    const clients = new Set();
+    const buffer = []; // últimos mensajes, para reenganchar con Last-Event-ID
+    const lastStatus = new Map(); // clave de pool -> estado visto
+    let seq = 0;
+    let heartbeat = null;
+
+    function format(m) {
+        return `id: ${m.id}\nevent: ${m.event}\ndata: ${JSON.stringify(m.data, (_k, v) => typeof v === 'bigint' ? v.toString() : v)}\n\n`;
+    }
+
+    function broadcast(event, data) {
+        const m = { id: ++seq, event, data };
+        buffer.push(m);
+        if (buffer.length > bufferSize) buffer.shift();
+        const chunk = format(m);
+        for (const res of clients) {
+            try { res.write(chunk); } catch (_) { clients.delete(res); }
+        }
+        return m;
+    }
+
+    // Recalcula estados y emite los que cambiaron (los vencimientos dependen del reloj)
+    function checkStatuses(now = Math.floor(Date.now() / 1000)) {
+        for (const p of pools.values()) {
+            if (!p || p.goal == null || p.raised == null) continue;
+            let status;
+            try { status = poolStatus(p, now); } catch (_) { continue; }
+            const key = p.key ?? String(p.id);
+            const prev = lastStatus.get(key);
+            lastStatus.set(key, status);
+            if (prev && prev !== status) {
+                broadcast('status', { poolId: Number(p.id), contract: p.contract ?? null, key, from: prev, to: status });
+            }
+        }
+    }
+
+    // Callback para el indexador: un mensaje por evento aplicado
+    function onPoolEvent(change) {
+        broadcast(change.registryKey ? 'registry' : 'pool', change);
+        checkStatuses();
+    }
+
+    function handler(req, res) {
+        res.writeHead(200, {
+            'Content-Type': 'text/event-stream',
+            'Cache-Control': 'no-cache',
+            Connection: 'keep-alive',
+            'X-Accel-Buffering': 'no'
+        });
+        res.write('retry: 5000\n\n');
+
+        // Reenvía lo que se perdió durante la reconexión
+        const lastId = Number(req.headers['last-event-id'] || 0);
+        if (lastId > 0) buffer.filter(m => m.id > lastId).forEach(m => res.write(format(m)));
+
+        clients.add(res);
+        req.log?.debug('stream abierto', { operation: 'STREAM', clients: clients.size });
+        req.on('close', () => clients.delete(res));
+    }
+
+    function start() {
+        checkStatuses(); // línea base, sin emitir
+        if (!heartbeat) {
+            heartbeat = setInterval(() => {
+                checkStatuses();
+                for (const res of clients) {
+                    try { res.write(': ping\n\n'); } catch (_) { clients.delete(res); }
+                }
+            }, heartbeatMs);
+        }
+        logger?.debug('stream listo', { operation: 'STREAM' });
+    }
+
+    function stop() {
+        if (heartbeat) clearInterval(heartbeat);
+        heartbeat = null;
+        for (const res of clients) { try { res.end(); } catch (_) {} }
+        clients.clear();
+    }
+
+    return { handler, broadcast, onPoolEvent, checkStatuses, start, stop, clients };
+}
+
+module.exports = { createPoolStream };
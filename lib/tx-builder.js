@@ -0,0 +1,349 @@
+const StellarSdk = require('@stellar/stellar-sdk');
+
+const { Address, StrKey, nativeToScVal, scValToNative, SorobanRpc, xdr } = StellarSdk;
+
+// Códigos de `Error` en contracts/pool/src/lib.rs
+const CONTRACT_ERRORS = {
+    1: 'NotInitialized', 2: 'AlreadyInitialized', 3: 'PoolNotFound', 4: 'InvalidAmount',
+    5: 'InvalidDeadline', 6: 'PoolExpired', 7: 'AlreadyFinalized', 8: 'GoalNotReached',
+    9: 'NotCreator', 10: 'RefundNotAvailable', 11: 'NothingToRefund', 12: 'GoalExceeded',
+    13: 'InvalidMilestones', 14: 'HasMilestones', 15: 'NoMilestonePending', 16: 'MilestoneLapsed',
+    17: 'InvalidLimits', 18: 'BelowMinContribution', 19: 'MemberCapExceeded', 20: 'MaxMembersReached',
+    21: 'RegistryNotFound', 22: 'NotRegistryAdmin', 23: 'NotMember', 24: 'AlreadyMember',
+    25: 'PoolCancelled', 26: 'InvalidExtension', 27: 'NoExtensionPending', 28: 'ExtensionPending',
+    29: 'AlreadyVoted', 30: 'NotContributor'
+};
+
+class TxBuildError extends Error {
+    constructor(message, status = 400, details = undefined) {
+        super(message);
+        this.status = status;
+        this.details = details;
+    }
+}
+
+function contractErrorName(err) {
+    const m = /Error\(Contract, #(\d+)\)/.exec(String(err || ''));
+    return m ? (CONTRACT_ERRORS[m[1]] || `Contract#${m[1]}`) : null;
+}
+
+// --- validación de parámetros ---
+function address(v, name) {
+    const s = String(v || '');
+    if (!StrKey.isValidEd25519PublicKey(s) && !StrKey.isValidContract(s)) {
+        throw new TxBuildError(`${name} must be a G... or C... address`);
+    }
+    return s;
+}
+
+function account(v, name) {
+    const s = String(v || '');
+    if (!StrKey.isValidEd25519PublicKey(s)) throw new TxBuildError(`${name} must be a G... account`);
+    return s;
+}
+
+function positiveInt(v, name) {
+    if (!/^\d+$/.test(String(v ?? '')) || BigInt(v) <= 0n) throw new TxBuildError(`${name} must be a positive integer`);
+    return BigInt(v);
+}
+
+function nonNegativeInt(v, name) {
+    if (v == null) return 0n;
+    if (!/^\d+$/.test(String(v))) throw new TxBuildError(`${name} must be a non-negative integer`);
+    return BigInt(v);
+}
+
+// Hitos [{ percent_bps, deadline }]: la validación de fondo (suma 100 %, plazos crecientes) la hace el contrato
+function milestones(v) {
+    if (v == null) return [];
+    if (!Array.isArray(v) || v.length > 10) throw new TxBuildError('milestones must be an array of up to 10 items');
+    return v.map((m, i) => ({
+        percent_bps: Number(positiveInt(m?.percent_bps, `milestones[${i}].percent_bps`)),
+        deadline: positiveInt(m?.deadline, `milestones[${i}].deadline`)
+    }));
+}
+
+// Límites de aporte { min_contribution, max_contribution_per_member, max_members }; ausentes = 0 (sin límite)
+function limits(v = {}) {
+    if (v == null || typeof v !== 'object' || Array.isArray(v)) throw new TxBuildError('limits must be an object');
+    const maxMembers = nonNegativeInt(v.max_members, 'limits.max_members');
+    if (maxMembers > 0xffffffffn) throw new TxBuildError('limits.max_members is too large');
+    return {
+        min_contribution: nonNegativeInt(v.min_contribution, 'limits.min_contribution'),
+        max_contribution_per_member: nonNegativeInt(v.max_contribution_per_member, 'limits.max_contribution_per_member'),
+        max_members: Number(maxMembers)
+    };
+}
+
+const addr = (s) => nativeToScVal(Address.fromString(s), { type: 'address' });
+const u32 = (n) => nativeToScVal(Number(n), { type: 'u32' });
+const milestoneVec = (list) => xdr.ScVal.scvVec(list.map(m => nativeToScVal(m, {
+    type: { percent_bps: ['symbol', 'u32'], deadline: ['symbol', 'u64'] }
+})));
+// Registro de miembros opcional: Option<u32> (None = pool abierta)
+const registryOpt = (v) => v == null || v === '' ? xdr.ScVal.scvVoid() : u32(positiveInt(v, 'registry'));
+// Mayoría para extender el plazo, en puntos base de lo recaudado (0 = mayoría simple)
+function quorumBps(v) {
+    const n = nonNegativeInt(v, 'extension_quorum_bps');
+    if (n > 10_000n) throw new TxBuildError('extension_quorum_bps must be at most 10000');
+    return Number(n);
+}
+
+// PoolTerms { limits, milestones, registry, extension_quorum_bps } de create_pool
+const poolTerms = (p) => nativeToScVal({
+    limits: nativeToScVal(limits(p.limits ?? {}), {
+        type: {
+            min_contribution: ['symbol', 'i128'],
+            max_contribution_per_member: ['symbol', 'i128'],
+            max_members: ['symbol', 'u32']
+        }
+    }),
+    milestones: milestoneVec(milestones(p.milestones)),
+    registry: registryOpt(p.registry),
+    extension_quorum_bps: quorumBps(p.extension_quorum_bps)
+}, {
+    type: { limits: ['symbol'], milestones: ['symbol'], registry: ['symbol'], extension_quorum_bps: ['symbol', 'u32'] }
+});
+
+// ¿create_pool pide algo distinto de los terms por defecto?
+function hasTerms(p) {
+    const l = p.limits || {};
+    return (Array.isArray(p.milestones) && p.milestones.length > 0)
+        || [l.min_contribution, l.max_contribution_per_member, l.max_members].some(v => v != null && BigInt(v) !== 0n)
+        || (p.registry != null && p.registry !== '')
+        || (p.extension_quorum_bps != null && Number(p.extension_quorum_bps) !== 0);
+}
+
+// Cada acción: quién firma (source) y los argumentos del contrato
+const ACTIONS = {
+    create_pool: (p) => {
+        const creator = account(p.creator, 'creator');
+        const deadline = positiveInt(p.deadline, 'deadline');
+        return {
+            source: creator,
+            fn: 'create_pool',
+            args: [
+                addr(creator),
+                addr(address(p.token, 'token')),
+                addr(address(p.supplier, 'supplier')),
+                nativeToScVal(positiveInt(p.goal, 'goal'), { type: 'i128' }),
+                nativeToScVal(deadline, { type: 'u64' }),
+                poolTerms(p)
+            ]
+        };
+    },
+    contribute: (p) => {
+        const from = account(p.from, 'from');
+        return {
+            source: from,
+            fn: 'contribute',
+            args: [u32(positiveInt(p.poolId, 'poolId')), addr(from), nativeToScVal(positiveInt(p.amount, 'amount'), { type: 'i128' })]
+        };
+    },
+    finalize: (p) => {
+        const creator = account(p.creator, 'creator');
+        return { source: creator, fn: 'finalize', args: [u32(positiveInt(p.poolId, 'poolId')), addr(creator)] };
+    },
+    release_milestone: (p) => {
+        const creator = account(p.creator, 'creator');
+        return { source: creator, fn: 'release_milestone', args: [u32(positiveInt(p.poolId, 'poolId')), addr(creator)] };
+    },
+    cancel_pool: (p) => {
+        const creator = account(p.creator, 'creator');
+        return { source: creator, fn: 'cancel_pool', args: [u32(positiveInt(p.poolId, 'poolId')), addr(creator)] };
+    },
+    propose_extension: (p) => {
+        const proposer = account(p.proposer, 'proposer');
+        return {
+            source: proposer,
+            fn: 'propose_extension',
+            args: [u32(positiveInt(p.poolId, 'poolId')), addr(proposer), nativeToScVal(positiveInt(p.deadline, 'deadline'), { type: 'u64' })]
+        };
+    },
+    vote_extension: (p) => {
+        const voter = account(p.voter, 'voter');
+        return { source: voter, fn: 'vote_extension', args: [u32(positiveInt(p.poolId, 'poolId')), addr(voter)] };
+    },
+    refund: (p) => {
+        const user = account(p.user, 'user');
+        return { source: user, fn: 'refund', args: [u32(positiveInt(p.poolId, 'poolId')), addr(user)] };
+    },
+    create_registry: (p) => {
+        const admin = account(p.admin, 'admin');
+        return { source: admin, fn: 'create_registry', args: [addr(admin)] };
+    },
+    add_member: (p) => {
+        const admin = account(p.admin, 'admin');
+        return {
+            source: admin,
+            fn: 'add_member',
+            args: [u32(positiveInt(p.registryId, 'registryId')), addr(admin), addr(account(p.member, 'member'))]
+        };
+    },
+    remove_member: (p) => {
+        const admin = account(p.admin, 'admin');
+        return {
+            source: admin,
+            fn: 'remove_member',
+            args: [u32(positiveInt(p.registryId, 'registryId')), addr(admin), addr(account(p.member, 'member'))]
+        };
+    }
+};
+
+// Arma transacciones preparadas (sin firmar) y retransmite las firmadas.
+// Mismo pipeline que usaba index.html: build -> simulate -> assemble; la firma queda en el cliente.
+// create_pool y create_registry van siempre al contrato principal (una pool solo puede usar
+// registros de su mismo contrato); el resto acepta `contract` si es uno de `contractIds`.
+// `poolTokens()` devuelve los tokens de las pools indexadas: solo a esos se les retransmite un approve.
+// Con `poolTerms: false` (contrato desplegado antes de PoolTerms) create_pool va sin el argumento `terms`.
+function createTxBuilder({
+    rpcUrl, contractId, contractIds = null, networkPassphrase, timeoutSec = 600, poolTokens = () => [], poolTerms = true
+}) {
+    const server = new SorobanRpc.Server(rpcUrl, { allowHttp: true });
+    const contracts = contractIds && contractIds.length ? contractIds.map(String) : [contractId];
+
+    function targetContract(action, params) {
+        if (action === 'create_pool' || action === 'create_registry' || params.contract == null) return contractId;
+        const c = String(params.contract);
+        if (!contracts.includes(c)) throw new TxBuildError(`contract is not indexed by this server: ${c}`);
+        return c;
+    }
+
+    async function prepare(source, contract, fn, args) {
+        const acct = await server.getAccount(source).catch(() => {
+            throw new TxBuildError(`source account not found: ${source}`, 404);
+        });
+        const tx = new StellarSdk.TransactionBuilder(acct, { fee: StellarSdk.BASE_FEE, networkPassphrase })
+            .addOperation(StellarSdk.Operation.invokeContractFunction({ contract, function: fn, args }))
+            .setTimeout(timeoutSec)
+            .build();
+
+        const sim = await server.simulateTransaction(tx);
+        if (SorobanRpc.Api.isSimulationError(sim)) {
+            throw new TxBuildError('simulation failed', 422, {
+                latestLedger: sim.latestLedger,
+                contractError: contractErrorName(sim.error),
+                error: String(sim.error)
+            });
+        }
+
+        const prepared = SorobanRpc.assembleTransaction(tx, sim).build();
+        const retval = sim.result?.retval;
+        let returnValue = null;
+        try { returnValue = retval ? scValToNative(retval) : null; } catch (_) {}
+
+        return {
+            xdr: prepared.toXDR(),
+            networkPassphrase,
+            source,
+            fee: {
+                base: String(StellarSdk.BASE_FEE),
+                resource: String(sim.minResourceFee),
+                total: String(prepared.fee)
+            },
+            simulation: {
+                latestLedger: sim.latestLedger,
+                returnValue: typeof returnValue === 'bigint' ? returnValue.toString() : returnValue,
+                authEntries: sim.result?.auth?.length || 0,
+                events: sim.events?.length || 0,
+                restorePreamble: SorobanRpc.Api.isSimulationRestore(sim)
+            }
+        };
+    }
+
+    // contribute usa transfer_from: si falta allowance se entrega primero el approve
+    async function allowance(token, from, spender) {
+        const src = new StellarSdk.Account(from, '0');
+        const tx = new StellarSdk.TransactionBuilder(src, { fee: StellarSdk.BASE_FEE, networkPassphrase })
+            .addOperation(StellarSdk.Operation.invokeContractFunction({
+                contract: token, function: 'allowance', args: [addr(from), addr(spender)]
+            }))
+            .setTimeout(30)
+            .build();
+        const sim = await server.simulateTransaction(tx);
+        if (SorobanRpc.Api.isSimulationError(sim) || !sim.result?.retval) return 0n;
+        return BigInt(scValToNative(sim.result.retval));
+    }
+
+    async function build(action, params = {}, { token = null } = {}) {
+        const make = ACTIONS[action];
+        if (!make) throw new TxBuildError(`unknown action: ${action}`, 404);
+        const { source, fn, args } = make(params);
+        const contract = targetContract(action, params);
+
+        if (action === 'create_pool' && !poolTerms) {
+            if (hasTerms(params)) {
+                throw new TxBuildError('the deployed contract does not support milestones, limits, registries or extension quorum; redeploy it (see README)', 409);
+            }
+            args.pop();
+        }
+
+        if (action === 'contribute' && token) {
+            const amount = BigInt(params.amount);
+            if ((await allowance(token, source, contract)) < amount) {
+                const { sequence } = await server.getLatestLedger();
+                const approve = await prepare(source, token, 'approve', [
+                    addr(source), addr(contract),
+                    nativeToScVal(amount, { type: 'i128' }),
+                    u32(sequence + Number(params.approveLedgers || 1000))
+                ]);
+                // El cliente firma y envía el approve, y vuelve a pedir build/contribute
+                return { action: 'approve', next: 'contribute', ...approve };
+            }
+        }
+
+        return { action, contract, ...(await prepare(source, contract, fn, args)) };
+    }
+
+    // Solo retransmite invocaciones a nuestros contratos, o el approve previo a contribute:
+    // sobre el token de una pool indexada y con uno de nuestros contratos como spender
+    function checkSubmittable(tx) {
+        const ops = tx.operations || [];
+        if (ops.length !== 1 || ops[0].type !== 'invokeHostFunction') {
+            throw new TxBuildError('only single contract invocations can be submitted');
+        }
+        const fn = ops[0].func;
+        if (fn.switch().name !== 'hostFunctionTypeInvokeContract') {
+            throw new TxBuildError('only contract invocations can be submitted');
+        }
+        const ic = fn.invokeContract();
+        const target = Address.fromScAddress(ic.contractAddress()).toString();
+        const name = ic.functionName().toString();
+        if (contracts.includes(target)) return { contract: target, fn: name };
+        if (name !== 'approve' || !poolTokens().map(String).includes(target)) {
+            throw new TxBuildError('transaction does not target the pool contract');
+        }
+        const spender = ic.args()[1];
+        let spenderId = null;
+        try { spenderId = spender ? Address.fromScVal(spender).toString() : null; } catch (_) {}
+        if (!contracts.includes(spenderId)) throw new TxBuildError('approve spender must be the pool contract');
+        return { contract: target, fn: name };
+    }
+
+    async function submit(signedXdr) {
+        let tx;
+        try {
+            tx = StellarSdk.TransactionBuilder.fromXDR(String(signedXdr || ''), networkPassphrase);
+        } catch (e) {
+            throw new TxBuildError('invalid transaction XDR');
+        }
+        if (!tx.signatures || tx.signatures.length === 0) throw new TxBuildError('transaction is not signed');
+        const target = checkSubmittable(tx);
+
+        const sent = await server.sendTransaction(tx);
+        return {
+            hash: sent.hash,
+            status: sent.status, // PENDING | DUPLICATE | TRY_AGAIN_LATER | ERROR
+            latestLedger: sent.latestLedger,
+            source: tx.source,
+            ...target,
+            error: sent.status === 'ERROR' && sent.errorResult
+                ? sent.errorResult.result().switch().name
+                : null
+        };
+    }
+
+    return { build, submit, server };
+}
+
+module.exports = { CONTRACT_ERRORS, TxBuildError, contractErrorName, createTxBuilder };
@@ -0,0 +1,134 @@
+// Seguimiento de transacciones enviadas: el servidor consulta getTransaction hasta que
+// se asientan (SUCCESS / FAILED) o se dan por perdidas (NOT_FOUND tras `maxAgeMs`).
+// Cada cambio de estado queda en el tx log y, si hay callbackUrl, se notifica por POST.
+// getTransaction va por JSON-RPC directo: solo se lee status/ledger y no se decodifica XDR.
+
+const SETTLED = new Set(['SUCCESS', 'FAILED', 'NOT_FOUND']);
+
+function createTxTracker({
+    rpcUrl,
+    store,
+    logger = null,
+    callbackUrl = null,
+    intervalMs = 3000,
+    maxAgeMs = 10 * 60_000,
+    fetchImpl = globalThis.fetch
+}) {
+    let timer = null;
+    let running = false;
+
+    function transition(row, status, extra = {}) {
+        const now = new Date().toISOString();
+        const next = {
+            ...row,
+            ...extra,
+            status,
+            updated_at: now,
+            settled_at: SETTLED.has(status) ? now : null
+        };
+        store.saveTracked(next);
+        store.appendTx({
+            operation: 'TX_STATUS',
+            details: { hash: row.hash, fn: row.fn, source: row.source, from: row.status || null, to: status, ledger: next.ledger ?? null },
+            status,
+            error: next.error ?? null
+        });
+        logger?.info('tx cambió de estado', { operation: 'TX_STATUS', hash: row.hash, from: row.status || null, to: status });
+        return next;
+    }
+
+    async function notify(row) {
+        if (!callbackUrl || !fetchImpl) return row;
+        let callbackStatus;
+        try {
+            const r = await fetchImpl(callbackUrl, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({
+                    hash: row.hash, status: row.status, fn: row.fn, source: row.source,
+                    ledger: row.ledger, error: row.error, settledAt: row.settled_at
+                })
+            });
+            callbackStatus = r.ok ? 'sent' : `http_${r.status}`;
+        } catch (e) {
+            callbackStatus = 'error';
+            logger?.warn('callback de tx falló', { operation: 'TX_STATUS', hash: row.hash, error: String(e.message || e) });
+        }
+        const next = { ...row, callback_status: callbackStatus };
+        store.saveTracked(next);
+        return next;
+    }
+
+    async function getTransaction(hash) {
+        const r = await fetchImpl(rpcUrl, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ jsonrpc: '2.0', id: 1, method: 'getTransaction', params: { hash } })
+        });
+        if (!r.ok) throw new Error(`rpc http ${r.status}`);
+        const body = await r.json();
+        if (body.error) throw new Error(body.error.message || 'rpc error');
+        return body.result;
+    }
+
+    // Empieza a seguir un hash (idempotente)
+    function track(hash, { fn = null, source = null } = {}) {
+        const h = String(hash || '').toLowerCase();
+        if (!/^[0-9a-f]{64}$/.test(h)) throw new Error('invalid transaction hash');
+        const cur = store.getTracked(h);
+        if (cur) return cur;
+        const now = new Date().toISOString();
+        return transition({ hash: h, fn, source, status: null, created_at: now }, 'PENDING');
+    }
+
+    async function check(row) {
+        const res = await getTransaction(row.hash);
+        if (res.status === 'SUCCESS' || res.status === 'FAILED') {
+            const settled = transition(row, res.status, {
+                ledger: res.ledger ?? null,
+                error: res.status === 'FAILED' ? 'transaction failed on-chain' : null
+            });
+            return notify(settled);
+        }
+        if (Date.now() - Date.parse(row.created_at) > maxAgeMs) {
+            return notify(transition(row, 'NOT_FOUND', { error: 'not found before timeout' }));
+        }
+        return row;
+    }
+
+    // Una pasada sobre todas las pendientes
+    async function tick() {
+        if (running) return;
+        running = true;
+        try {
+            for (const row of store.pendingTracked()) {
+                try {
+                    await check(row);
+                } catch (e) {
+                    logger?.warn('getTransaction falló', { operation: 'TX_STATUS', hash: row.hash, error: String(e.message || e) });
+                }
+            }
+        } finally {
+            running = false;
+        }
+    }
+
+    function start() {
+        if (!timer) timer = setInterval(() => { tick().catch(() => {}); }, intervalMs);
+        return api;
+    }
+
+    function stop() {
+        if (timer) clearInterval(timer);
+        timer = null;
+    }
+
+    const api = {
+        track, tick, start, stop,
+        get: (hash) => store.getTracked(String(hash || '').toLowerCase()),
+        pendingCount: () => store.pendingTracked().length
+    };
+    return api;
+}
+
+module.exports = { SETTLED, createTxTracker };
@@ -0,0 +1,121 @@
+const crypto = require('crypto');
+
+// Vouchers: comprobante de lo que el proveedor le debe a cada aportante de una pool finalizada.
+// El tope por aportante es su aporte neto (ctr - rf) según el ledger de eventos.
+
+const QR_PREFIX = 'agrocoop-voucher:v1';
+// Crockford base32: sin I, L, O, U para que se pueda dictar/teclear
+const ALPHABET = '0123456789ABCDEFGHJKMNPQRSTVWXYZ';
+
+class VoucherError extends Error {
+    constructor(message, status = 400, details = undefined) {
+        super(message);
+        this.status = status;
+        this.details = details;
+    }
+}
+
+function newCode() {
+    const bytes = crypto.randomBytes(12);
+    let out = '';
+    for (const b of bytes) out += ALPHABET[b & 31];
+    return `AGRO-${out.slice(0, 4)}-${out.slice(4, 8)}-${out.slice(8, 12)}`;
+}
+
+function createVoucherService({ store, secret }) {
+    if (!secret) throw new Error('voucher secret is required');
+
+    function sign(v) {
+        return crypto.createHmac('sha256', secret)
+            .update(`${v.code}|${v.poolId}|${v.contributor}|${v.amount}`)
+            .digest('base64url')
+            .slice(0, 22);
+    }
+
+    // Lo que se codifica en el QR: suficiente para verificar sin más datos
+    function withQr(v) {
+        return { ...v, qrPayload: `${QR_PREFIX}:${v.code}:${v.poolId}:${v.amount}:${sign(v)}` };
+    }
+
+    // Clave de pool (lib/pool-keys.js): "3" o "C...:3" si es de otro contrato
+    const poolRef = (pool) => String(pool.key ?? pool.id);
+
+    function quota(pool, address) {
+        const { contributed, refunded } = store.memberBalance(poolRef(pool), address);
+        const share = contributed - refunded;
+        const issued = store.vouchersByMember(poolRef(pool), address).reduce((acc, v) => acc + BigInt(v.amount), 0n);
+        let reason = null;
+        if (!pool.finalized) reason = 'not_finalized';
+        else if (share <= 0n) reason = 'no_contribution';
+        const available = reason ? 0n : (share > issued ? share - issued : 0n);
+        return { share, issued, available, reason };
+    }
+
+    function issue(pool, address, amount) {
+        if (!/^\d+$/.test(String(amount ?? '')) || BigInt(amount) <= 0n) {
+            throw new VoucherError('amount must be a positive integer (stroops)');
+        }
+        const q = quota(pool, address);
+        if (q.reason) throw new VoucherError(q.reason, 409);
+        if (BigInt(amount) > q.available) {
+            throw new VoucherError('amount exceeds contributor share', 409, { available: q.available.toString() });
+        }
+        const v = store.issueVoucher({
+            code: newCode(),
+            poolId: poolRef(pool),
+            contributor: address,
+            supplier: pool.supplier,
+            amount: String(BigInt(amount))
+        }, q.share.toString());
+        // Otro voucher entró entre la consulta y la inserción
+        if (!v) throw new VoucherError('amount exceeds contributor share', 409);
+        return withQr(v);
+    }
+
+    // Acepta el código o el payload completo del QR
+    function parse(input) {
+        const s = String(input || '').trim();
+        if (s.startsWith(`${QR_PREFIX}:`)) {
+            // La clave de pool puede traer ':' (C...:3): código al inicio, monto y firma al final
+            const parts = s.slice(QR_PREFIX.length + 1).split(':');
+            const [code, amount, sig] = [parts[0], parts[parts.length - 2], parts[parts.length - 1]];
+            return { code, poolId: parts.slice(1, -2).join(':'), amount, sig };
+        }
+        return { code: s.toUpperCase(), sig: null };
+    }
+
+    function verify(input) {
+        const q = parse(input);
+        const v = store.getVoucher(q.code);
+        if (!v) return { valid: false, reason: 'not_found', voucher: null };
+        // Con payload de QR: firma y datos deben calzar con lo emitido
+        if (q.sig != null) {
+            const expected = sign(v);
+            const ok = q.sig.length === expected.length
+                && crypto.timingSafeEqual(Buffer.from(q.sig), Buffer.from(expected))
+                && String(q.poolId) === String(v.poolId)
+                && String(q.amount) === String(v.amount);
+            if (!ok) return { valid: false, reason: 'bad_signature', voucher: null };
+        }
+        if (v.status !== 'issued') return { valid: false, reason: v.status, voucher: withQr(v) };
+        return { valid: true, reason: null, voucher: withQr(v) };
+    }
+
+    // Solo el proveedor de la pool canjea, y una sola vez
+    function redeem(input, supplierAddress) {
+        const { code } = parse(input);
+        const check = verify(input);
+        if (!check.voucher) throw new VoucherError(check.reason, check.reason === 'not_found' ? 404 : 400);
+        if (check.voucher.supplier !== String(supplierAddress)) {
+            throw new VoucherError('only the pool supplier can redeem this voucher', 403);
+        }
+        if (!check.valid || !store.redeemVoucher(code, supplierAddress)) {
+            throw new VoucherError('voucher already redeemed', 409);
+        }
+        return withQr(store.getVoucher(code));
+    }
+
+    return { quota, issue, verify, redeem, withQr };
+}
+
+module.exports = { QR_PREFIX, VoucherError, createVoucherService };
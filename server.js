@@ -1,177 +1,248 @@
 const express = require('express');
 const cors = require('cors');
-const path = require('path');
 const fs = require('fs');
+const path = require('path');
+const { openStore } = require('./lib/db');
+const { createEventSource } = require('./lib/event-source');
+const { createIndexer, poolStatus, isActionable, refundableFor } = require('./lib/indexer');
+const { createLogger, requestLogger, normalizeLevel } = require('./lib/logger');
+const { normalizeMetadata } = require('./lib/metadata');
+const { AuthError, createAuth } = require('./lib/auth');
+const { createChainReader, createCacheChainReader, createTokenRegistry, diffPool } = require('./lib/chain');
+const { TxBuildError, createTxBuilder } = require('./lib/tx-builder');
+const { createTxTracker } = require('./lib/tx-tracker');
+const { createPoolStream } = require('./lib/stream');
+const { VoucherError, createVoucherService } = require('./lib/vouchers');
+const { computeAllocation, allocationToCsv } = require('./lib/allocation');
+const { createPriceSource, createPriceOracle } = require('./lib/price');
+const { loadNetworkProfile } = require('./lib/network');
+const { NotifyError, normalizeSubscription, createChannels, createNotifier } = require('./lib/notifier');
+const { AdminError, parseAdminAddresses, createAdmin } = require('./lib/admin');
 
 // Cargar variables desde .env.local (si existe)
 try { require('dotenv').config({ path: '.env.local' }); } catch (_) {}
 
 const ADMIN_CODE = process.env.POOLS_ADMIN_CODE || process.env.ADMIN_CODE || '';
 
-// 👇 importa el objeto completo y luego extraes lo que necesitas
-const StellarSdk = require('@stellar/stellar-sdk');
-
-const {
-  Address,
-  TransactionBuilder,
-  Operation,
-  BASE_FEE,
-  Networks,
-  nativeToScVal,
-  scValToNative,
-  xdr
-} = StellarSdk;
-
 const app = express();
 const PORT = 3000;
 
-// Configuración de persistencia
+// Perfil de red (config/networks.json); NETWORK=local apunta al contenedor stellar/quickstart
+const NETWORK_PROFILE = loadNetworkProfile({
+    file: process.env.NETWORKS_FILE || path.join(__dirname, 'config', 'networks.json'),
+    name: process.env.NETWORK
+});
+const NETWORK = NETWORK_PROFILE.network;
+
+// Configuración de persistencia: una base por red para no mezclar pools de testnet y local
 const DATA_DIR = path.join(__dirname, 'data');
+const LEGACY_DB_FILE = path.join(DATA_DIR, 'agrocoop.db');
+const DB_FILE = process.env.DB_FILE
+    || (NETWORK === 'testnet' && fs.existsSync(LEGACY_DB_FILE) ? LEGACY_DB_FILE : path.join(DATA_DIR, `agrocoop-${NETWORK}.db`));
+// Archivos JSON antiguos (de testnet): solo se leen una vez para migrar a SQLite
 const STATE_FILE = path.join(DATA_DIR, 'pools_state.json');
 const TX_LOG = path.join(DATA_DIR, 'tx_log.json');
 
 // Configuración del contrato y RPC
-const CONTRACT_ID = process.env.CONTRACT_ID || 'CBAID77FC57C6LNDGPS2RTTWA6RZY72LXJYQMLZMX3NBO4VSWGXLTVT2';
-// 🟢 usa el namespace garantizado del objeto importado
-const RPC_URL = process.env.SOROBAN_RPC || 'https://soroban-testnet.stellar.org';
-const rpcServer = new StellarSdk.SorobanRpc.Server(RPC_URL, { allowHttp: true });
-
-// Cache en memoria: en prod, usa Redis/SQLite
-const pools = new Map();
-let lastScannedLedger = 0;
+const CONTRACT_ID = NETWORK_PROFILE.contractId;
+const RPC_URL = NETWORK_PROFILE.rpcUrl;
+const NETWORK_PASSPHRASE = NETWORK_PROFILE.networkPassphrase;
+// Fuente de eventos: RPC en vivo o fixtures grabados (EVENT_SOURCE=fixture, EVENT_FIXTURES=<ruta>)
+const eventSource = createEventSource({
+    kind: process.env.EVENT_SOURCE || 'rpc',
+    rpcUrl: RPC_URL,
+    fixtures: process.env.EVENT_FIXTURES
+});
+
+// Logs JSON en data/logs (rotación diaria y por tamaño)
+const logger = createLogger({
+    dir: process.env.LOG_DIR || path.join(DATA_DIR, 'logs'),
+    level: process.env.LOG_LEVEL || 'info',
+    maxBytes: Number(process.env.LOG_MAX_BYTES || 5 * 1024 * 1024),
+    retentionDays: Number(process.env.LOG_RETENTION_DAYS || 14),
+    mirrorToConsole: process.env.LOG_CONSOLE === '1'
+});
+
+// Persistencia en SQLite; el Map del indexador sigue siendo la cache en memoria
+const store = openStore(DB_FILE);
+const indexer = createIndexer({
+    source: eventSource,
+    store,
+    contractIds: NETWORK_PROFILE.contractIds,
+    logger,
+    onEvent: (change) => {
+        poolStream.onPoolEvent(change);
+        notifier.onPoolEvent(change);
+    }
+});
+const pools = indexer.pools;
+// Clave de pool por (contrato, id): "3" en el contrato principal, "C...:3" en los demás
+const poolKeys = indexer.keys;
+// SSE para dashboards: eventos aplicados y cambios de estado
+const poolStream = createPoolStream({ pools, logger });
 const hidden = new Set(); // ids ocultos
 
-// Buffer para contribuciones huérfanas (cuando la pool no existe aún)
-const pendingRaised = new Map(); // pid -> BigInt
+// Lectura de get_pool para validar lo que registra el frontend (con fixtures: el estado indexado)
+const chainReader = eventSource.kind === 'fixture'
+    ? createCacheChainReader(pools, poolKeys.key)
+    : createChainReader({ rpcUrl: RPC_URL, contractId: CONTRACT_ID, networkPassphrase: NETWORK_PASSPHRASE });
+// Decimales y símbolo del token de cada pool (XLM, USDC u otro SAC)
+const tokens = createTokenRegistry({ reader: chainReader, nativeTokenId: NETWORK_PROFILE.nativeTokenId, logger });
 
-// Candado para evitar resyncs solapados
-let hydratingPromise = null;
+// Transacciones preparadas en el servidor: el cliente solo firma (Freighter, móvil, CLI)
+const txBuilder = createTxBuilder({
+    rpcUrl: RPC_URL,
+    contractId: CONTRACT_ID,
+    contractIds: NETWORK_PROFILE.contractIds,
+    networkPassphrase: NETWORK_PASSPHRASE,
+    poolTokens: () => [...new Set([...pools.values()].map(p => p.token).filter(Boolean))],
+    poolTerms: NETWORK_PROFILE.poolTerms
+});
 
-// Helpers para parsing robusto de eventos
-function scvFromAny(x) {
-    try {
-        if (xdr.ScVal.isValid(x)) return x;
-    } catch(_) {}
-    try { return typeof x === 'string' ? xdr.ScVal.fromXDR(x, 'base64') : null; } catch(_) { return null; }
-}
+// El servidor sigue los hashes enviados hasta que se asientan (TX_CALLBACK_URL recibe el resultado)
+const txTracker = createTxTracker({
+    rpcUrl: RPC_URL,
+    store,
+    logger,
+    callbackUrl: process.env.TX_CALLBACK_URL || null,
+    intervalMs: Number(process.env.TX_POLL_MS || 3000)
+});
+const TX_TRACK_MAX_PENDING = Number(process.env.TX_TRACK_MAX_PENDING || 200);
 
-function scvToNativeSafe(scvLike) {
-    try {
-        const scv = scvFromAny(scvLike) || scvLike;
-        return scValToNative(scv);
-    } catch(_) { return null; }
+// Vouchers firmados con VOUCHER_SECRET (si no hay, se genera uno y queda en la base)
+function voucherSecret() {
+    if (process.env.VOUCHER_SECRET) return process.env.VOUCHER_SECRET;
+    let secret = store.getMeta('voucherSecret');
+    if (!secret) {
+        secret = require('crypto').randomBytes(32).toString('hex');
+        store.setMeta('voucherSecret', secret);
+    }
+    return secret;
 }
+const vouchers = createVoucherService({ store, secret: voucherSecret() });
+
+// Precio XLM/CLP: feed HTTP (PRICE_SOURCE=http) o archivo local (PRICE_SOURCE=file, PRICE_FILE=<ruta>)
+const priceOracle = createPriceOracle({
+    source: createPriceSource({
+        kind: process.env.PRICE_SOURCE || 'http',
+        url: process.env.PRICE_FEED_URL,
+        path: process.env.PRICE_FEED_PATH,
+        file: process.env.PRICE_FILE
+    }),
+    store,
+    logger,
+    ttlMs: Number(process.env.PRICE_TTL_MS || 5 * 60_000),
+    fallbackRate: Number(process.env.PRICE_FALLBACK_RATE || 100)
+});
+
+// Avisos a miembros suscritos (NOTIFY_CHANNELS=console,file,webhook,smtp)
+const notifier = createNotifier({
+    pools,
+    store,
+    tokens,
+    logger,
+    channels: createChannels(process.env.NOTIFY_CHANNELS ?? 'file', process.env, { dataDir: DATA_DIR }),
+    remindBeforeSec: Number(process.env.NOTIFY_REMIND_BEFORE_H || 24) * 3600,
+    intervalMs: Number(process.env.NOTIFY_INTERVAL_MS || 60_000)
+});
+
+// Sesiones firmadas con la clave Stellar del usuario (challenge/response vía Freighter)
+const auth = createAuth({ store, sessionTtlMs: Number(process.env.AUTH_SESSION_TTL_MS || 12 * 3600_000) });
+
+// Administración por roles (auditor < moderator < superadmin), con audit de cada acción
+const admin = createAdmin({
+    store,
+    auth,
+    legacyCode: ADMIN_CODE,
+    seed: parseAdminAddresses(process.env.ADMIN_ADDRESSES),
+    logger
+});
+
+const hydrateFromEvents = (fromLedger) => indexer.hydrate(fromLedger);
 
-function topicSym(t) {
-    const v = scvToNativeSafe(t);
-    // Símbolos en Soroban suelen decodificar a string
-    return typeof v === 'string' ? v : (v && v.sym) ? v.sym : String(v);
+// --- Persistencia en SQLite ---
+function saveState() {
+  indexer.save();
 }
 
-function topicNum(t) {
-    const v = scvToNativeSafe(t);
-    const n = Number(v);
-    return Number.isFinite(n) ? n : null;
+function savePool(p) {
+  try { store.savePool(p); } catch (e) { /* Error persistiendo pool */ }
 }
 
-function extractAmount(payload) {
+function loadState() {
     try {
-        if (typeof payload === 'bigint') return payload;
-        if (typeof payload === 'number') return BigInt(payload);
-        if (typeof payload === 'string' && /^-?\d+$/.test(payload)) return BigInt(payload);
-        if (payload && typeof payload === 'object') {
-            for (const k of ['amount','delta','value','raised','contribution']) {
-                if (payload[k] != null) return BigInt(payload[k]);
-            }
-            if (Array.isArray(payload)) {
-                for (const it of payload) {
-                    const v = extractAmount(it);
-                    if (v !== 0n) return v;
-                }
-            }
-        }
-    } catch(_) {}
-    return 0n;
+        if (NETWORK === 'testnet') store.migrateFromJson(STATE_FILE, TX_LOG);
+        indexer.load();
+        hidden.clear();
+        store.loadHidden().forEach(id => hidden.add(String(id)));
+        // Estado restaurado
+    } catch (e) {
+        // Error cargando estado
+    }
 }
 
-function extractPoolId(native, topics) {
-    // 1) payload con id
-    if (native && typeof native === 'object') {
-        for (const k of ['id','pool','pool_id','poolId']) {
-            const n = Number(native[k]);
-            if (Number.isFinite(n) && n > 0) return n;
-        }
-    }
-    // 2) tópico 1 suele ser el id
-    if (Array.isArray(topics) && topics[1]) {
-        const n = topicNum(topics[1]);
-        if (Number.isFinite(n) && n > 0) return n;
-    }
-    // 3) búsqueda en todos los tópicos
-    if (Array.isArray(topics)) {
-        for (const t of topics) {
-            const n = topicNum(t);
-            if (Number.isFinite(n) && n > 0) return n;
-        }
-    }
-    return null;
+function appendTxLog(entry) {
+    store.appendTx(entry);
 }
 
-// --- Persistencia simple en disco ---
-function ensureDataDir() {
-  try { fs.mkdirSync(DATA_DIR, { recursive: true }); } catch (_) {}
+// Adjunta metadatos off-chain (tabla aparte: un resync nunca los pisa)
+function withMetadata(list) {
+    const meta = store.allMetadata();
+    return list.map(p => ({ ...p, metadata: meta.get(p.key ?? String(p.id)) || null }));
 }
 
-function saveState() {
-  try {
-    ensureDataDir();
-    const payload = {
-      lastScannedLedger,
-      pools: [...pools.values()], // guardamos objetos ya "planos"
-      hidden: [...hidden],
-      savedAt: new Date().toISOString()
-    };
-    fs.writeFileSync(STATE_FILE, JSON.stringify(payload, null, 2));
-  } catch (e) {
-    // Error persistiendo pools_state
-  }
+// :id de las rutas de pool ("3" o "C...:3"); responde 400 si no es válido
+function poolRef(req, res) {
+    const ref = poolKeys.parse(req.params.id);
+    if (!ref) res.status(400).json({ error: 'invalid pool id' });
+    return ref;
 }
 
-function loadState() {
+// Contrasta un payload de /api/pools/register con get_pool.
+// Devuelve la pool a guardar o { status, error }. Si la pool ya está indexada se conserva
+// su estado (lo mantienen los eventos) y solo se toma el nombre; si no, se parte de get_pool.
+// Quien llama debe hidratar antes, para que los eventos pendientes no se sumen sobre el snapshot.
+async function reconcilePool(submitted, address, strict = false) {
+    const ref = poolKeys.parse(submitted?.id ?? submitted?.pool_id ?? submitted?.poolId, submitted?.contract);
+    if (!ref) return { status: 400, error: 'missing pool.id or contract not indexed' };
+
+    let onchain;
     try {
-        if (!fs.existsSync(STATE_FILE)) return;
-        const raw = fs.readFileSync(STATE_FILE, 'utf8');
-        const data = JSON.parse(raw);
-        pools.clear();
-        for (const p of data.pools || []) pools.set(String(p.id), p);
-        lastScannedLedger = Math.max(0, Number(data.lastScannedLedger || 0));
-        (data.hidden || []).forEach(id => hidden.add(String(id)));
-        // Estado restaurado
+        onchain = await chainReader.getPool(ref.id, ref.contract);
     } catch (e) {
-        // Error cargando pools_state
+        // Sin poder leer la cadena no se acepta nada del cliente
+        return { status: 503, error: `could not read pool from chain: ${e.message || e}` };
+    }
+    if (!onchain) return { status: 404, error: 'pool not found on chain' };
+    if (onchain.creator !== String(address)) {
+        return { status: 403, error: 'only the pool creator can register or update it' };
     }
-}
 
-function appendTxLog(entry) {
-    ensureDataDir();
-    let arr = [];
-    try { if (fs.existsSync(TX_LOG)) arr = JSON.parse(fs.readFileSync(TX_LOG,'utf8')); } catch {}
-    arr.push(entry);
-    fs.writeFileSync(TX_LOG, JSON.stringify(arr, null, 2));
+    const diff = diffPool(submitted, onchain);
+    const corrected = Object.keys(diff).length > 0;
+    if (corrected && strict) return { status: 409, error: 'pool disagrees with chain state', diff };
+
+    const cur = pools.get(ref.key);
+    const name = submitted.name != null ? String(submitted.name) : (cur?.name || '');
+    if (cur) return { pool: { ...cur, name }, corrected, diff };
+    return { pool: { ...onchain, contract: ref.contract, key: ref.key, name }, corrected, diff };
 }
 
-// Helper para validar el código de administrador
-function checkAdminCode(req) {
-  const code = (req.headers['x-admin-code'] || req.body?.code || req.query?.code || '').toString();
-  return ADMIN_CODE && code && code === ADMIN_CODE;
+// Guarda (una sola vez) la tasa XLM/CLP con que el creador calculó la meta
+function recordCreationRate(poolId, submitted) {
+    const rate = Number(submitted?.xlmClpRate);
+    if (!Number.isFinite(rate) || rate <= 0) return;
+    try {
+        store.setCreationRate(poolId, {
+            rate,
+            source: submitted.xlmClpRateSource ? String(submitted.xlmClpRateSource) : 'client',
+            updatedAt: submitted.xlmClpRateUpdatedAt ? String(submitted.xlmClpRateUpdatedAt) : null
+        });
+    } catch (_) {}
 }
 
-// Middleware de logging personalizado
-app.use((req, res, next) => {
-    const timestamp = new Date().toISOString();
-    // Request logged
-    next();
-});
+// Middleware de logging: request id (X-Request-Id) + línea por request
+app.use(requestLogger(logger));
 
 // Middleware para parsear JSON
 app.use(express.json());
@@ -183,8 +254,10 @@ loadState();
 app.use(cors());
 app.use(express.static(path.join(__dirname, 'public')));
 
-// Guardado cada 30s como respaldo
-setInterval(() => { try { saveState(); } catch(_){} }, 30_000);
+// Retoma las transacciones pendientes que quedaron de la ejecución anterior
+txTracker.start();
+poolStream.start();
+notifier.start();
 
 // Poll suave para futuros eventos (cada 60s)
 setInterval(() => { 
@@ -193,172 +266,36 @@ setInterval(() => {
     } catch(_){} 
 }, 60_000);
 
-// Reconstruye estado desde eventos
-async function hydrateFromEvents(fromLedger) {
-    if (hydratingPromise) return hydratingPromise;
-    hydratingPromise = (async () => {
-        try {
-        const latest = await rpcServer.getLatestLedger();
-        const DEFAULT_WINDOW = 150_000; // ajusta si quieres más ventana
-
-        // Nunca uses 0. Para "full resync" arranca en 1 y deja que el RPC te diga el mínimo real.
-        const clampPos = (x) => Math.max(1, Number(x || 0));
-        let start = (fromLedger === 0)
-          ? 1
-          : clampPos(fromLedger || lastScannedLedger || (latest.sequence - DEFAULT_WINDOW));
-
-        // Hidratando pools
-
-        let paginationToken;
-        let processed = 0;
-
-        retryFetch:
-        do {
-            let ev;
-            try {
-              ev = await rpcServer.getEvents({
-                startLedger: start,
-                filters: [{ type: 'contract', contractIds: [CONTRACT_ID] }],
-                paginationToken
-              });
-            } catch (e) {
-              const msg = String(e?.message || e);
-              // 1) startLedger <= 0
-              if (/must be positive/i.test(msg)) {
-                start = clampPos(latest.sequence - DEFAULT_WINDOW);
-                // Ajustando startLedger
-                paginationToken = undefined;
-                continue retryFetch;
-              }
-              // 2) Fuera del rango permitido -> extrae mínimo/máximo del error
-              const m = /within the ledger range:\s*(\d+)\s*-\s*(\d+)/i.exec(msg);
-              if (m) {
-                const min = Number(m[1]), max = Number(m[2]);
-                if (start < min) {
-                  // Ajuste startLedger
-                  start = min; paginationToken = undefined; continue retryFetch;
-                }
-                if (start > max) {
-                  const newStart = Math.max(min, max - DEFAULT_WINDOW);
-                  // Ajuste startLedger
-                  start = newStart; paginationToken = undefined; continue retryFetch;
-                }
-              }
-              // Otro error: registra y sal
-              // Error hidratando
-              break;
-            }
-
-            for (const e of ev.events || []) {
-                const raw = e.value?.xdr || e.value || e.data?.xdr || e.data;
-                const native = scvToNativeSafe(raw);
-                const topics = e.topics || e.topic || [];
-
-                const tag = topics[0] ? topicSym(topics[0]) : null;
-                const pid = extractPoolId(native, topics);
-                if (!pid) { continue; } // sin id no podemos aplicar
-
-                const key = String(pid);
-                const tagNorm = (tag || '').toLowerCase();
-
-                // PC / PoolCreated
-                if (tagNorm === 'pc' || /pool.*created|created|create_pool/i.test(tagNorm)) {
-                    const prev = pools.get(key);
-                    const obj = {
-                        ...(prev || {}),  // 👈 conserva prev.name si existía
-                        ...((typeof native === 'object' && native) || {}),
-                        id: pid,
-                        goal: String((native?.goal ?? native?.target ?? 0)),
-                        raised: String((native?.raised ?? 0)),
-                        deadline: Number(native?.deadline ?? 0),
-                        finalized: Boolean(native?.finalized),
-                    };
-                    
-                    // Aplicar contribuciones huérfanas si las hay
-                    const buff = pendingRaised.get(key);
-                    if (typeof buff === 'bigint' && buff !== 0n) {
-                        obj.raised = (BigInt(obj.raised ?? '0') + buff).toString();
-                        pendingRaised.delete(key);
-                        // Aplicando contribuciones huérfanas
-                    }
-                    
-                    pools.set(key, obj);
-                    // Pool creada
-                }
-                // CTR / Contributed
-                else if (tagNorm === 'ctr' || /contribut|contribute/i.test(tagNorm)) {
-                    const p = pools.get(key);
-                    const delta = extractAmount(native);  // soporta struct/tupla/string
-                    if (p) {
-                        const prev = BigInt(p.raised ?? '0');
-                        p.raised = (prev + delta).toString();
-                        // Contribución aplicada
-                    } else {
-                        // Contribución huérfana: la pool no existe aún, la guardamos para después
-                        const cur = pendingRaised.get(key) ?? 0n;
-                        pendingRaised.set(key, cur + delta);
-                        // Contribución huérfana
-                    }
-                }
-                // FN / Finalized
-                else if (tagNorm === 'fn' || /finaliz/i.test(tagNorm)) {
-                    const p = pools.get(key);
-                    if (p) { p.finalized = true; }
-                } else if (pid) {
-                    // ⚙️ Fallback genérico: si veo un id pero no reconozco tag,
-                    // creo/actualizo un contenedor con campos mínimos
-                    const prev = pools.get(key) || { id: pid, goal: "0", raised: "0", deadline: 0, finalized: false };
-                    const p = { ...prev };
-                    // si el payload trae algo útil, copiarlo
-                    if (native && typeof native === 'object') {
-                        if (native.goal     != null) p.goal     = String(native.goal);
-                        if (native.raised   != null) p.raised   = String(native.raised);
-                        if (native.deadline != null) p.deadline = Number(native.deadline);
-                        if (native.finalized!= null) p.finalized= Boolean(native.finalized);
-                    }
-                    pools.set(key, p);
-                }
-
-                // Asegura avanzar el puntero de ledger con nombres alternativos
-                lastScannedLedger = Math.max(
-                    lastScannedLedger,
-                    e.ledger ?? e.ledgerSequence ?? 0
-                );
-                processed++;
-            }
-
-            paginationToken = ev.paginationToken || ev.cursor || null;
-        } while (paginationToken);
-
-        // 🔸 Persistimos a disco cada vez que hidratamos algo
-        if (processed > 0) saveState();
-
-        // Hidratación completada
-        } catch (e) {
-            // Error hidratando
-        } finally {
-            hydratingPromise = null;
-        }
-    })();
-    return hydratingPromise;
-}
-
 // Ruta principal - servir index.html
 app.get('/', (req, res) => {
     res.sendFile(path.join(__dirname, 'public', 'index.html'));
 });
 
+// Tokens del perfil de red (y los de pools ya indexadas) para el selector de creación
+app.get('/api/tokens', async (req, res) => {
+    try {
+        const fromPools = [...pools.values()].map(p => p.token);
+        res.json({ tokens: await tokens.list([...NETWORK_PROFILE.tokenIds, ...fromPools]) });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
 // Ruta para obtener información del contrato (para futuras extensiones)
 app.get('/api/contract-info', (req, res) => {
-    // Solicitud de información del contrato
-    const contractInfo = {
-        contractId: 'CBAID77FC57C6LNDGPS2RTTWA6RZY72LXJYQMLZMX3NBO4VSWGXLTVT2',
-        tokenId: 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC',
-        network: 'testnet',
-        rpcUrl: 'https://soroban-testnet.stellar.org'
-    };
-    // Información del contrato enviada
-    res.json(contractInfo);
+    // El frontend arranca con esto: contrato, token y endpoints de la red activa
+    res.json({
+        network: NETWORK,
+        contractId: CONTRACT_ID,
+        contractIds: NETWORK_PROFILE.contractIds,
+        tokenId: NETWORK_PROFILE.tokenId,
+        nativeTokenId: NETWORK_PROFILE.nativeTokenId,
+        rpcUrl: RPC_URL,
+        horizonUrl: NETWORK_PROFILE.horizonUrl,
+        friendbotUrl: NETWORK_PROFILE.friendbotUrl,
+        networkPassphrase: NETWORK_PASSPHRASE,
+        poolTerms: NETWORK_PROFILE.poolTerms
+    });
 });
 
 // Endpoints públicos para pools
@@ -368,20 +305,15 @@ app.get('/api/pools', async (req, res) => {
 
         if (String(req.query.resync) === '1') {
             // Resync solicitado
-            lastScannedLedger = 0; // fuerza hydrateFromEvents(0)
+            indexer.state.lastScannedLedger = 0; // fuerza hydrateFromEvents(0)
         }
 
         const doFull = String(req.query.resync) === '1';
         await hydrateFromEvents(doFull ? 0 : undefined);
         
         const now = Math.floor(Date.now()/1000);
-        const listAll = [...pools.values()].map(p => ({
-            ...p,
-            status: p.finalized ? 'finalized'
-                  : (now > Number(p.deadline) ? 'expired'
-                  : (BigInt(p.raised) >= BigInt(p.goal) ? 'funded' : 'active'))
-        }));
-        const list = listAll.filter(p => !hidden.has(String(p.id)));
+        const listAll = [...pools.values()].map(p => ({ ...p, status: poolStatus(p, now) }));
+        const list = listAll.filter(p => !hidden.has(p.key ?? String(p.id)));
         
         // Pools en memoria
 
@@ -390,53 +322,28 @@ app.get('/api/pools', async (req, res) => {
         if (list.length === 0 && String(req.query._retried) !== '1') {
             // Lista vacía, forzando segunda pasada
             await hydrateFromEvents(0);
-            const retry = [...pools.values()].map(p => ({ ...p, status: p.finalized ? 'finalized' :
-                (now > Number(p.deadline) ? 'expired' : (BigInt(p.raised) >= BigInt(p.goal) ? 'funded' : 'active')) }));
+            const retry = [...pools.values()].map(p => ({ ...p, status: poolStatus(p, now) }));
             
             // ⛔️ FIX: aplicar filtro de "hidden" también en el fallback
-            const retryVisible = retry.filter(p => !hidden.has(String(p.id)));
+            const retryVisible = retry.filter(p => !hidden.has(p.key ?? String(p.id)));
             
             if (retryVisible.length > 0) {
                 const showAll = String(req.query.all) === '1';
-                const actionable = retryVisible.filter(p => {
-                    const raised = BigInt(p.raised);
-                    const goal   = BigInt(p.goal);
-                    const expired = now > Number(p.deadline);
-                    const funded  = raised >= goal;
-                    // 1) activa
-                    if (!p.finalized && !expired) return true;
-                    // 2) vencida y reembolsable
-                    if (!p.finalized && expired && raised < goal) return true;
-                    // 3) financiada pero no finalizada
-                    if (!p.finalized && funded) return true;
-                    return false;
-                });
+                const actionable = retryVisible.filter(p => isActionable(p, now));
                 const out = showAll ? retryVisible : actionable;
                 // Retry exitoso
-                return res.json({ pools: out });
+                return res.json({ pools: await tokens.annotate(withMetadata(out)) });
             }
         }
         
         // 👇 filtrar "accionables" salvo que pidan todo con ?all=1
         const mode = String(req.query.filter || '').toLowerCase();
         const showAll = String(req.query.all) === '1' || mode === 'simple';
-        const actionable = list.filter(p => {
-          const raised = BigInt(p.raised);
-          const goal   = BigInt(p.goal);
-          const expired = now > Number(p.deadline);
-          const funded  = raised >= goal;
-          // 1) activa
-          if (!p.finalized && !expired) return true;
-          // 2) vencida y reembolsable
-          if (!p.finalized && expired && raised < goal) return true;
-          // 3) financiada pero no finalizada
-          if (!p.finalized && funded) return true;
-          return false;
-        });
+        const actionable = list.filter(p => isActionable(p, now));
 
         const out = showAll ? list : actionable; // con ?filter=simple, showAll = true
         // Enviando pools
-        res.json({ pools: out });
+        res.json({ pools: await tokens.annotate(withMetadata(out)) });
     } catch (e) {
         // Error obteniendo pools
         res.status(500).json({ error: String(e) });
@@ -445,80 +352,483 @@ app.get('/api/pools', async (req, res) => {
 
 app.get('/api/pools/:id', async (req, res) => {
     try {
-        const poolId = String(Number(req.params.id));
+        const ref = poolRef(req, res);
+        if (!ref) return;
         // Solicitud de pool específica
         await hydrateFromEvents();
-        const p = pools.get(poolId);
+        const p = pools.get(ref.key);
         if (!p) {
             // Pool no encontrada
             return res.status(404).json({ error: 'Pool not found' });
         }
         // Enviando pool
-        res.json(p);
+        const [out] = await tokens.annotate([{ ...p, metadata: store.getMetadata(ref.key) }]);
+        res.json(out);
     } catch (e) {
         // Error obteniendo pool
         res.status(500).json({ error: String(e) });
     }
 });
 
+// Auth: pide un nonce para firmar con Freighter (signMessage)
+app.post('/api/auth/challenge', (req, res) => {
+    try {
+        res.json(auth.challenge(String(req.body?.address || '')));
+    } catch (e) {
+        res.status(e instanceof AuthError ? e.status : 500).json({ error: e.message });
+    }
+});
+
+// Auth: verifica la firma del nonce y entrega un token de sesión (Authorization: Bearer)
+app.post('/api/auth/verify', (req, res) => {
+    try {
+        const { address, nonce, signature } = req.body || {};
+        const session = auth.verify({ address: String(address || ''), nonce, signature });
+        req.log.info('sesión iniciada', { operation: 'AUTH', address: session.address });
+        res.json(session);
+    } catch (e) {
+        if (e instanceof AuthError) {
+            req.log.warn('firma rechazada', { operation: 'AUTH', reason: e.message });
+            return res.status(e.status).json({ error: e.message });
+        }
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+app.get('/api/auth/session', auth.requireAuth, (req, res) => {
+    res.json({ address: req.auth.address });
+});
+
+app.post('/api/auth/logout', (req, res) => {
+    auth.logout(auth.tokenFrom(req));
+    res.json({ ok: true });
+});
+
+// Metadatos de producto/entrega de una pool
+app.get('/api/pools/:id/metadata', (req, res) => {
+    const ref = poolRef(req, res);
+    if (!ref) return;
+    res.json({ poolId: ref.id, contract: ref.contract, metadata: store.getMetadata(ref.key) });
+});
+
+// Edición de metadatos: solo el creador on-chain de la pool, con sesión firmada
+app.put('/api/pools/:id/metadata', auth.requireAuth, async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        const { metadata } = req.body || {};
+        const address = req.auth.address;
+
+        await hydrateFromEvents();
+        let p = pools.get(poolId);
+        if (!p) {
+            // Aún sin indexar: el creador sale de get_pool, nunca del cliente
+            try {
+                p = await chainReader.getPool(ref.id, ref.contract);
+            } catch (e) {
+                return res.status(503).json({ error: `could not read pool from chain: ${e.message || e}` });
+            }
+            if (!p) return res.status(404).json({ error: 'Pool not found' });
+        }
+        if (!p.creator || String(p.creator) !== String(address)) {
+            return res.status(403).json({ error: 'only the pool creator can edit metadata' });
+        }
+
+        const { value, errors } = normalizeMetadata(metadata);
+        if (errors.length) return res.status(400).json({ error: 'invalid metadata', details: errors });
+
+        const saved = store.saveMetadata(poolId, value, String(address));
+        req.log.info('metadata actualizada', { operation: 'POOL_METADATA', poolId, fields: Object.keys(value) });
+        res.json({ ok: true, poolId: ref.id, contract: ref.contract, metadata: saved });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Contribuciones de una pool (reconstruidas desde eventos ctr)
+app.get('/api/pools/:id/contributions', async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        await hydrateFromEvents();
+        const contributions = store.contributionsByPool(poolId);
+        const total = contributions.reduce((acc, c) => acc + BigInt(c.amount), 0n);
+        res.json({ poolId: ref.id, contract: ref.contract, total: total.toString(), contributions });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Reparto pro-rata de lo comprado (aporte, cantidad y CLP por miembro). ?format=csv para el día de entrega
+app.get('/api/pools/:id/allocation', async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        await hydrateFromEvents();
+        const p = pools.get(poolId);
+        if (!p) return res.status(404).json({ error: 'Pool not found' });
+
+        const allocation = computeAllocation(store.memberTotals(poolId), store.getMetadata(poolId));
+        if (String(req.query.format || '').toLowerCase() === 'csv') {
+            res.setHeader('Content-Type', 'text/csv; charset=utf-8');
+            res.setHeader('Content-Disposition', `attachment; filename="pool-${poolId.replace(':', '-')}-reparto.csv"`);
+            return res.send(allocationToCsv(allocation));
+        }
+        res.json({ poolId: ref.id, contract: ref.contract, finalized: Boolean(p.finalized), raised: p.raised, ...allocation });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Cuánto puede reclamar todavía un miembro: pool cancelada, vencida sin llegar a la meta o con un hito vencido
+app.get('/api/pools/:id/refundable/:address', async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        const address = String(req.params.address || '').trim();
+        if (!address) return res.status(400).json({ error: 'missing address' });
+
+        await hydrateFromEvents();
+        const p = pools.get(poolId);
+        if (!p) return res.status(404).json({ error: 'Pool not found' });
+
+        const now = Math.floor(Date.now()/1000);
+        const { contributed, refunded } = store.memberBalance(poolId, address);
+        const { refundable, reason } = refundableFor(p, { contributed, refunded }, now);
+
+        res.json({
+            poolId: ref.id,
+            contract: ref.contract,
+            address,
+            contributed: contributed.toString(),
+            refunded: refunded.toString(),
+            refundable: refundable.toString(),
+            eligible: !reason,
+            reason
+        });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Cuánto puede emitir en vouchers un aportante (aporte neto menos lo ya emitido)
+app.get('/api/pools/:id/vouchers/quota/:address', async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        await hydrateFromEvents();
+        const p = pools.get(poolId);
+        if (!p) return res.status(404).json({ error: 'Pool not found' });
+
+        const q = vouchers.quota(p, req.params.address);
+        res.json({
+            poolId: ref.id,
+            contract: ref.contract,
+            address: req.params.address,
+            share: q.share.toString(),
+            issued: q.issued.toString(),
+            available: q.available.toString(),
+            eligible: !q.reason,
+            reason: q.reason
+        });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Emite un voucher para el aportante autenticado (pool finalizada)
+app.post('/api/pools/:id/vouchers', auth.requireAuth, async (req, res) => {
+    try {
+        const ref = poolRef(req, res);
+        if (!ref) return;
+        const poolId = ref.key;
+        await hydrateFromEvents();
+        const p = pools.get(poolId);
+        if (!p) return res.status(404).json({ error: 'Pool not found' });
+
+        const v = vouchers.issue(p, req.auth.address, req.body?.amount);
+        req.log.info('voucher emitido', { operation: 'VOUCHER_ISSUE', poolId, code: v.code, amount: v.amount });
+        res.status(201).json(v);
+    } catch (e) {
+        if (e instanceof VoucherError) return res.status(e.status).json({ error: e.message, ...(e.details || {}) });
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Verificación pública: código o payload del QR
+app.get('/api/vouchers/:code', (req, res) => {
+    const r = vouchers.verify(req.params.code);
+    res.status(r.reason === 'not_found' ? 404 : 200).json(r);
+});
+
+// Canje (un solo uso) por el proveedor de la pool
+app.post('/api/vouchers/:code/redeem', auth.requireAuth, (req, res) => {
+    try {
+        const v = vouchers.redeem(req.params.code, req.auth.address);
+        req.log.info('voucher canjeado', { operation: 'VOUCHER_REDEEM', poolId: v.poolId, code: v.code });
+        res.json({ ok: true, voucher: v });
+    } catch (e) {
+        if (e instanceof VoucherError) return res.status(e.status).json({ error: e.message });
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+app.get('/api/accounts/:address/vouchers', (req, res) => {
+    const list = store.vouchersByAccount(req.params.address).map(vouchers.withQr);
+    res.json({ address: req.params.address, vouchers: list });
+});
+
+// Contribuciones de una cuenta en todas las pools
+app.get('/api/accounts/:address/contributions', async (req, res) => {
+    try {
+        const address = String(req.params.address || '').trim();
+        if (!address) return res.status(400).json({ error: 'missing address' });
+        await hydrateFromEvents();
+        const contributions = store.contributionsByAccount(address);
+        const byPool = {};
+        for (const c of contributions) {
+            byPool[c.poolId] = (BigInt(byPool[c.poolId] ?? 0) + BigInt(c.amount)).toString();
+        }
+        res.json({ address, byPool, contributions });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Registros de miembros reconstruidos desde los eventos rg/ma/mr
+app.get('/api/registries', async (req, res) => {
+    try {
+        await hydrateFromEvents();
+        const list = [...indexer.registries.values()].map(r => ({
+            id: r.id, contract: r.contract, key: r.key, admin: r.admin, members: r.members.size
+        }));
+        res.json({ registries: list });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Miembros de un registro (:id "2" o "C...:2"); ?address=G... agrega isMember
+app.get('/api/registries/:id/members', async (req, res) => {
+    try {
+        const ref = poolKeys.parse(req.params.id);
+        if (!ref) return res.status(400).json({ error: 'invalid registry id' });
+        await hydrateFromEvents();
+        const r = indexer.registries.get(ref.key);
+        if (!r) return res.status(404).json({ error: 'Registry not found' });
+        const members = store.registryMembers(ref.key);
+        const address = String(req.query.address || '').trim();
+        res.json({
+            registryId: ref.id,
+            contract: ref.contract,
+            admin: r.admin,
+            members,
+            ...(address ? { isMember: r.members.has(address) } : {})
+        });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
 // Endpoint para logging de operaciones del frontend
 app.post('/api/log', (req, res) => {
-    const { level, message, data, operation } = req.body;
-    const timestamp = new Date().toISOString();
-    
-    const logMessage = `[${timestamp}] [${level.toUpperCase()}] [${operation || 'FRONTEND'}] ${message}`;
-    
-    // Log message processed
-    
-    res.json({ success: true, logged: true });
+    const { level, message, data, operation, relatedRequestId } = req.body || {};
+
+    req.log.log(normalizeLevel(level), message, {
+        source: 'frontend',
+        operation: operation || 'FRONTEND',
+        data: data ?? null,
+        relatedRequestId: relatedRequestId || null
+    });
+
+    res.json({ success: true, logged: true, requestId: req.id });
 });
 
 // Endpoint para logging de transacciones
 app.post('/api/log-transaction', (req, res) => {
-    const { operation, details, status, error } = req.body;
+    const { operation, details, status, error, relatedRequestId } = req.body || {};
     const timestamp = new Date().toISOString();
-    
-    // Transaction logged
-    
+
+    req.log.log(error || status === 'error' ? 'warn' : 'info', 'transaction', {
+        source: 'frontend',
+        operation: operation || 'TRANSACTION',
+        status: status ?? null,
+        details: details ?? null,
+        error: error || null,
+        relatedRequestId: relatedRequestId || null
+    });
+
     // 💾 persistir también
     appendTxLog({ timestamp, operation, details, status, error: error || null });
-    
-    res.json({ success: true, logged: true });
+
+    res.json({ success: true, logged: true, requestId: req.id });
 });
 
 // Endpoint para logging de errores específicos
 app.post('/api/log-error', (req, res) => {
-    const { error, context, stack } = req.body;
-    const timestamp = new Date().toISOString();
-    
-    // Error logged
-    
-    res.json({ success: true, logged: true });
+    const { error, context, stack, operation, relatedRequestId } = req.body || {};
+
+    req.log.error(String(error ?? 'unknown error'), {
+        source: 'frontend',
+        operation: operation || 'FRONTEND_ERROR',
+        context: context ?? null,
+        stack: stack ?? null,
+        relatedRequestId: relatedRequestId || null
+    });
+
+    res.json({ success: true, logged: true, requestId: req.id });
+});
+
+// Consulta de logs (solo admin): ?level=warn&operation=CREATE_POOL&from=...&to=...&requestId=...&limit=200
+app.get('/api/logs', admin.requireRole('auditor', 'logs.read'), (req, res) => {
+    const { level, operation, from, to, requestId, limit } = req.query;
+    const entries = logger.query({ level, operation, from, to, requestId, limit });
+    res.json({ count: entries.length, entries });
+});
+
+// Arma una transacción sin firmar: create_pool | contribute | finalize | release_milestone | refund
+app.post('/api/tx/build/:action', async (req, res) => {
+    try {
+        let params = req.body || {};
+        let token = null;
+        // poolId puede venir como clave "C...:3" o junto a `contract`
+        if (req.params.action !== 'create_pool' && params.poolId != null) {
+            const ref = poolKeys.parse(params.poolId, params.contract);
+            if (!ref) return res.status(400).json({ error: 'invalid poolId or contract not indexed' });
+            params = { ...params, poolId: ref.id, contract: ref.contract };
+            if (req.params.action === 'contribute') {
+                token = (await chainReader.getPool(ref.id, ref.contract).catch(() => null))?.token || null;
+            }
+        }
+        const built = await txBuilder.build(req.params.action, params, { token });
+        req.log.info('tx preparada', { operation: 'TX_BUILD', action: built.action, source: built.source });
+        res.json(built);
+    } catch (e) {
+        if (e instanceof TxBuildError) {
+            return res.status(e.status).json({ error: e.message, simulation: e.details });
+        }
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Retransmite una transacción firmada a la red
+app.post('/api/tx/submit', async (req, res) => {
+    try {
+        const sent = await txBuilder.submit(req.body?.xdr);
+        appendTxLog({
+            operation: 'TX_SUBMIT',
+            details: { hash: sent.hash, fn: sent.fn, contract: sent.contract, source: sent.source },
+            status: sent.status,
+            error: sent.error
+        });
+        req.log.info('tx enviada', { operation: 'TX_SUBMIT', hash: sent.hash, fn: sent.fn, status: sent.status });
+        if (sent.status === 'PENDING' || sent.status === 'DUPLICATE') {
+            txTracker.track(sent.hash, { fn: sent.fn, source: sent.source });
+        }
+        res.status(sent.status === 'ERROR' ? 422 : 200).json(sent);
+    } catch (e) {
+        if (e instanceof TxBuildError) return res.status(e.status).json({ error: e.message });
+        res.status(502).json({ error: String(e) });
+    }
+});
+
+// Tipo de cambio XLM/CLP (cacheado, con fecha de actualización)
+app.get('/api/price/xlm-clp', async (req, res) => {
+    try {
+        res.json(await priceOracle.get());
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Override manual del tipo de cambio (admin), útil sin red
+app.put('/api/price/xlm-clp', admin.requireRole('moderator', 'price.override'), async (req, res) => {
+    try {
+        const price = await priceOracle.setOverride(req.body?.rate, req.admin.actor);
+        req.log.info('override de precio', { operation: 'PRICE', rate: price.rate });
+        res.json(price);
+    } catch (e) {
+        res.status(400).json({ error: e.message });
+    }
+});
+
+app.delete('/api/price/xlm-clp', admin.requireRole('moderator', 'price.clear'), async (req, res) => {
+    res.json(await priceOracle.clearOverride());
+});
+
+// Suscripción a avisos de la cuenta autenticada (email y/o webhook; kinds opcional)
+app.get('/api/notifications/subscription', auth.requireAuth, (req, res) => {
+    res.json({ address: req.auth.address, subscription: store.getSubscription(req.auth.address) });
+});
+
+app.put('/api/notifications/subscription', auth.requireAuth, (req, res) => {
+    try {
+        const sub = store.saveSubscription(req.auth.address, normalizeSubscription(req.body));
+        req.log.info('suscripción actualizada', { operation: 'NOTIFY', address: req.auth.address });
+        res.json({ ok: true, subscription: sub });
+    } catch (e) {
+        if (e instanceof NotifyError) return res.status(e.status).json({ error: e.message });
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+app.delete('/api/notifications/subscription', auth.requireAuth, (req, res) => {
+    res.json({ ok: store.deleteSubscription(req.auth.address) });
 });
 
-// Endpoint para registrar pools desde el frontend
-app.post('/api/pools/register', (req, res) => {
+// Avisos enviados (o fallidos) a la cuenta autenticada
+app.get('/api/notifications', auth.requireAuth, (req, res) => {
+    res.json({ address: req.auth.address, notifications: store.notificationsByAddress(req.auth.address) });
+});
+
+// Stream SSE: `event: pool` por cada pc/ctr/rf/fn aplicado, `event: status` cuando cambia el estado
+app.get('/api/stream', (req, res) => poolStream.handler(req, res));
+
+// Seguir un hash enviado por otro camino (p. ej. el navegador directo al RPC)
+// Con sesión y con tope de pendientes: cada hash seguido es polling al RPC y un posible callback
+app.post('/api/tx/track', auth.requireAuth, (req, res) => {
+    try {
+        const { hash, fn } = req.body || {};
+        if (!txTracker.get(hash) && txTracker.pendingCount() >= TX_TRACK_MAX_PENDING) {
+            return res.status(429).json({ error: 'too many pending tracked transactions' });
+        }
+        res.json(txTracker.track(hash, { fn: fn ? String(fn) : null, source: req.auth.address }));
+    } catch (e) {
+        res.status(400).json({ error: e.message });
+    }
+});
+
+// Estado de una transacción seguida por el servidor
+app.get('/api/tx/:hash', (req, res) => {
+    const row = txTracker.get(req.params.hash);
+    if (!row) return res.status(404).json({ error: 'transaction not tracked' });
+    res.json(row);
+});
+
+// Endpoint para registrar pools desde el frontend.
+// Se hidrata primero y se contrasta con get_pool; del cliente solo se acepta el nombre.
+app.post('/api/pools/register', auth.requireAuth, async (req, res) => {
     try {
         const { pool } = req.body || {};
         if (!pool) return res.status(400).json({ error: 'missing pool' });
-        const id = String(pool.id ?? pool.pool_id ?? pool.poolId);
-        if (!id) return res.status(400).json({ error: 'missing pool.id' });
-
-        // normaliza algunos campos por si vienen como string/BigInt
-        const normalized = {
-            ...pool,
-            id: Number(id),
-            name: String(pool.name || ''),  // 👈 nuevo
-            goal: String(pool.goal),
-            raised: String(pool.raised ?? 0),
-            deadline: Number(pool.deadline),
-            finalized: Boolean(pool.finalized)
-        };
-
-        pools.set(String(normalized.id), normalized);
-        saveState(); // 💾 persiste en data/pools_state.json
-        // Pool registrada
-        res.json({ ok: true });
+        const strict = String(req.query.strict ?? req.body?.strict ?? '') === '1' || req.body?.strict === true;
+
+        await hydrateFromEvents();
+        const r = await reconcilePool(pool, req.auth.address, strict);
+        if (r.error) return res.status(r.status).json({ error: r.error, diff: r.diff });
+
+        pools.set(r.pool.key, r.pool);
+        savePool(r.pool); // 💾 persiste en SQLite
+        recordCreationRate(r.pool.key, pool);
+        if (r.corrected) {
+            req.log.warn('pool corregida con get_pool', { operation: 'POOL_REGISTER', poolId: r.pool.id, diff: r.diff });
+        }
+        res.json({ ok: true, corrected: r.corrected, diff: r.diff });
     } catch (e) {
         // Error registrando pool
         res.status(500).json({ error: String(e) });
@@ -526,60 +836,164 @@ app.post('/api/pools/register', (req, res) => {
 });
 
 // Registrar múltiples pools en lote (para bootstrap)
-app.post('/api/pools/register-batch', (req, res) => {
+app.post('/api/pools/register-batch', auth.requireAuth, async (req, res) => {
     try {
         const { pools: arr } = req.body || {};
         if (!Array.isArray(arr)) return res.status(400).json({ error: 'missing pools[]' });
+        const strict = String(req.query.strict ?? req.body?.strict ?? '') === '1' || req.body?.strict === true;
         
+        await hydrateFromEvents();
         let count = 0;
+        const rejected = [];
+        const corrected = [];
         for (const pool of arr) {
-            const id = String(pool.id ?? pool.pool_id ?? pool.poolId);
-            if (!id) continue;
-            pools.set(id, {
-                ...pool,
-                id: Number(id),
-                name: String(pool.name || ''),  // 👈 nuevo
-                goal: String(pool.goal ?? '0'),
-                raised: String(pool.raised ?? '0'),
-                deadline: Number(pool.deadline ?? 0),
-                finalized: Boolean(pool.finalized)
-            });
+            const r = await reconcilePool(pool, req.auth.address, strict);
+            if (r.error) {
+                rejected.push({ id: pool?.id ?? null, contract: pool?.contract ?? null, error: r.error, diff: r.diff });
+                continue;
+            }
+            pools.set(r.pool.key, r.pool);
+            recordCreationRate(r.pool.key, pool);
+            if (r.corrected) corrected.push({ id: r.pool.id, contract: r.pool.contract, diff: r.diff });
             count++;
         }
-        saveState();
+        saveState(); // una sola transacción para todo el lote
+        if (corrected.length) {
+            req.log.warn('pools corregidas con get_pool', { operation: 'POOL_REGISTER', corrected });
+        }
         // Bootstrap completado
-        res.json({ ok: true, count });
+        res.json({ ok: true, count, rejected, corrected });
     } catch (e) {
         // Error en bootstrap
         res.status(500).json({ error: String(e) });
     }
 });
 
-// Endpoints para ocultar/mostrar pools (requieren código admin)
-app.post('/api/pools/hide', (req, res) => {
-  if (!checkAdminCode(req)) return res.status(403).json({ error: 'forbidden' });
-  const id = String(req.body?.id ?? '');
-  if (!id) return res.status(400).json({ error: 'missing id' });
+// Endpoints para ocultar/mostrar pools (moderador)
+app.post('/api/pools/hide', admin.requireRole('moderator', 'pool.hide'), (req, res) => {
+  const raw = String(req.body?.id ?? '');
+  if (!raw) return res.status(400).json({ error: 'missing id' });
+  const id = poolKeys.parse(raw, req.body?.contract)?.key ?? raw;
   hidden.add(id);
-  saveState();
+  store.setHidden(id, true);
   return res.json({ ok: true });
 });
 
-app.post('/api/pools/unhide', (req, res) => {
-  if (!checkAdminCode(req)) return res.status(403).json({ error: 'forbidden' });
-  const id = String(req.body?.id ?? '');
-  if (!id) return res.status(400).json({ error: 'missing id' });
+app.post('/api/pools/unhide', admin.requireRole('moderator', 'pool.unhide'), (req, res) => {
+  const raw = String(req.body?.id ?? '');
+  if (!raw) return res.status(400).json({ error: 'missing id' });
+  const id = poolKeys.parse(raw, req.body?.contract)?.key ?? raw;
   hidden.delete(id);
-  saveState();
+  store.setHidden(id, false);
   return res.json({ ok: true });
 });
 
 // Listar pools ocultas (solo admin)
-app.get('/api/pools/hidden', (req, res) => {
-  if (!checkAdminCode(req)) return res.status(403).end();
+app.get('/api/pools/hidden', admin.requireRole('auditor', 'pool.hidden'), (req, res) => {
   return res.json({ ids: [...hidden] });
 });
 
+// --- Consola admin ---
+
+// Quién soy y con qué rol (sin audit: lo usa el frontend para decidir qué mostrar)
+app.get('/api/admin/me', (req, res) => {
+    const who = admin.identify(req);
+    if (!who) return res.status(403).json({ error: 'forbidden' });
+    res.json(who);
+});
+
+app.get('/api/admin/accounts', admin.requireRole('superadmin', 'account.list'), (req, res) => {
+    res.json({ accounts: admin.listAccounts() });
+});
+
+// Alta: { address, role } o { name, role }; para credenciales `key` se muestra solo aquí
+app.post('/api/admin/accounts', admin.requireRole('superadmin', 'account.create'), (req, res) => {
+    try {
+        const account = admin.createAccount(req.body || {}, req.admin.actor);
+        res.status(201).json(account);
+    } catch (e) {
+        if (e instanceof AdminError) return res.status(e.status).json({ error: e.message });
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+app.delete('/api/admin/accounts/:id', admin.requireRole('superadmin', 'account.delete'), (req, res) => {
+    try {
+        admin.removeAccount(req.params.id, req.admin.actor);
+        res.json({ ok: true });
+    } catch (e) {
+        if (e instanceof AdminError) return res.status(e.status).json({ error: e.message });
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// ?actor=...&action=pool.purge&limit=200
+app.get('/api/admin/audit', admin.requireRole('auditor', 'audit.read'), (req, res) => {
+    const { actor, action, limit } = req.query;
+    const entries = store.listAudit({ actor: actor || null, action: action || null, limit });
+    res.json({ count: entries.length, entries });
+});
+
+// Fuerza un rescan de eventos desde `fromLedger` (por defecto, todo el rango del RPC).
+// Los eventos ya aplicados se saltan, así que solo suma lo que se hubiera perdido.
+app.post('/api/admin/resync', admin.requireRole('moderator', 'indexer.resync'), async (req, res) => {
+    try {
+        const fromLedger = Math.max(0, Number(req.body?.fromLedger || 0));
+        const before = pools.size;
+        indexer.state.eventCursor = null;
+        await hydrateFromEvents(fromLedger);
+        req.log.info('resync forzado', { operation: 'ADMIN', fromLedger, pools: pools.size });
+        res.json({ ok: true, fromLedger, pools: pools.size, added: pools.size - before, lastScannedLedger: indexer.state.lastScannedLedger });
+    } catch (e) {
+        res.status(500).json({ error: String(e) });
+    }
+});
+
+// Edición de metadatos por un moderador (p. ej. corregir una categoría mal puesta)
+app.put('/api/admin/pools/:id/metadata', admin.requireRole('moderator', 'pool.metadata'), (req, res) => {
+    const ref = poolRef(req, res);
+    if (!ref) return;
+    if (!pools.has(ref.key)) return res.status(404).json({ error: 'Pool not found' });
+    const { value, errors } = normalizeMetadata(req.body?.metadata);
+    if (errors.length) return res.status(400).json({ error: 'invalid metadata', details: errors });
+    const saved = store.saveMetadata(ref.key, value, `admin:${req.admin.actor}`);
+    res.json({ ok: true, poolId: ref.id, contract: ref.contract, metadata: saved });
+});
+
+// Borra pools registradas por /api/pools/register que get_pool no reconoce.
+// { ids?: [...], dryRun?: bool }: sin ids revisa todas; con dryRun solo informa.
+app.post('/api/admin/pools/purge', admin.requireRole('moderator', 'pool.purge'), async (req, res) => {
+    const dryRun = Boolean(req.body?.dryRun);
+    const requested = Array.isArray(req.body?.ids) ? req.body.ids : null;
+    const refs = requested
+        ? requested.map(id => poolKeys.parse(id))
+        : [...pools.values()].map(p => poolKeys.parse(p.key ?? String(p.id)));
+    if (refs.some(r => !r)) return res.status(400).json({ error: 'invalid pool id' });
+
+    const purged = [];
+    const kept = [];
+    for (const ref of refs) {
+        let onchain;
+        try {
+            onchain = await chainReader.getPool(ref.id, ref.contract);
+        } catch (e) {
+            // Sin poder leer la cadena no se borra nada
+            return res.status(503).json({ error: `could not read pool from chain: ${e.message || e}`, purged });
+        }
+        if (onchain) { kept.push(ref.key); continue; }
+        if (!dryRun) {
+            pools.delete(ref.key);
+            hidden.delete(ref.key);
+            store.deletePool(ref.key);
+        }
+        purged.push(ref.key);
+    }
+    if (purged.length && !dryRun) {
+        req.log.warn('pools purgadas', { operation: 'ADMIN', purged });
+    }
+    res.json({ ok: true, dryRun, purged, kept: kept.length });
+});
+
 // Manejador de errores 404
 app.use((req, res) => {
     res.status(404).send(`
@@ -591,7 +1005,7 @@ app.use((req, res) => {
 
 // Iniciar servidor
 app.listen(PORT, () => {
-    console.log(`🚀 Servidor AgroCoop iniciado en puerto ${PORT} | Admin code: ${ADMIN_CODE ? '✅ Configurado' : '❌ No configurado'}`);
+    console.log(`🚀 Servidor AgroCoop iniciado en puerto ${PORT} | Admins: ${admin.listAccounts().length}${ADMIN_CODE ? ' + ADMIN_CODE' : ''}`);
 });
 
 // Hidratación inicial al arrancar
@@ -607,7 +1021,11 @@ app.listen(PORT, () => {
 
 // Manejo de cierre del servidor
 process.on('SIGINT', () => {
+    txTracker.stop();
+    poolStream.stop();
+    notifier.stop();
     try { saveState(); } catch(_) {}
+    store.close();
     // Cerrando servidor
     process.exit(0);
 });
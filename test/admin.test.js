@@ -0,0 +1,82 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { hashSecret, verifySecret, parseAdminAddresses, createAdmin } = require('../lib/admin');
+
+const ALICE = 'GAIH3ULLFQ4DGSECF2AR555KZ4KNDGEKN4AFI4SU2M7B43MGK3QJZNSR';
+const BOB = 'GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H';
+
+// Store en memoria con la interfaz admin de lib/db.js
+function memoryStore() {
+    const accounts = new Map();
+    const audit = [];
+    return {
+        getAdminAccount: id => accounts.get(id) || null,
+        listAdminAccounts: () => [...accounts.values()].map(({ secret_hash, ...a }) => a),
+        saveAdminAccount: a => accounts.set(a.id, { id: a.id, kind: a.kind, role: a.role, secret_hash: a.secretHash ?? null }),
+        deleteAdminAccount: id => accounts.delete(id),
+        appendAudit: e => audit.push(e),
+        audit
+    };
+}
+
+// Sesiones de prueba: "Bearer <address>"
+const auth = {
+    tokenFrom: req => (req.headers.authorization || '').replace(/^Bearer /, '') || null,
+    authenticate: token => token || null
+};
+
+function run(mw, req) {
+    const listeners = [];
+    const res = {
+        statusCode: 200,
+        status(c) { this.statusCode = c; return this; },
+        json(body) { this.body = body; listeners.forEach(f => f()); return this; },
+        on: (ev, f) => listeners.push(f)
+    };
+    let nextCalled = false;
+    mw({ method: 'POST', path: '/x', params: {}, body: {}, ...req }, res, () => { nextCalled = true; });
+    if (nextCalled) res.json({ ok: true });
+    return { res, nextCalled };
+}
+
+test('roles por dirección y credencial, sin leer el código desde query', () => {
+    assert.ok(verifySecret('s3cret', hashSecret('s3cret')));
+    assert.ok(!verifySecret('otro', hashSecret('s3cret')));
+    assert.deepEqual(parseAdminAddresses(`${ALICE}:superadmin, ${BOB}`), [
+        { id: ALICE, role: 'superadmin' }, { id: BOB, role: 'moderator' }
+    ]);
+
+    const store = memoryStore();
+    const admin = createAdmin({ store, auth, legacyCode: 'legacy', seed: parseAdminAddresses(`${ALICE}:superadmin`) });
+    const { key } = admin.createAccount({ name: 'lector', role: 'auditor' }, ALICE);
+
+    assert.deepEqual(admin.identify({ headers: { authorization: `Bearer ${ALICE}` } }), { actor: ALICE, role: 'superadmin' });
+    assert.deepEqual(admin.identify({ headers: { 'x-admin-key': key } }), { actor: 'lector', role: 'auditor' });
+    assert.equal(admin.identify({ headers: { 'x-admin-key': 'lector:mal' } }), null);
+    assert.equal(admin.identify({ headers: { authorization: `Bearer ${BOB}` } }), null);
+    assert.equal(admin.identify({ headers: {}, query: { code: 'legacy' }, body: { code: 'legacy' } }), null);
+    assert.equal(admin.identify({ headers: { 'x-admin-code': 'legacy' } }).role, 'superadmin');
+    assert.throws(() => admin.removeAccount(ALICE, ALICE), /own account/);
+});
+
+test('cada acción admin queda auditada, también las denegadas', () => {
+    const store = memoryStore();
+    const admin = createAdmin({ store, auth, seed: [{ id: ALICE, role: 'moderator' }] });
+    const { key } = admin.createAccount({ name: 'lector', role: 'auditor' }, 'env');
+
+    const denied = run(admin.requireRole('moderator', 'pool.hide'), { headers: { 'x-admin-key': key }, body: { id: '3' } });
+    assert.equal(denied.res.statusCode, 403);
+    assert.equal(denied.nextCalled, false);
+
+    const ok = run(admin.requireRole('moderator', 'pool.hide'), {
+        headers: { authorization: `Bearer ${ALICE}` }, body: { id: '3', code: 'no-se-guarda' }
+    });
+    assert.ok(ok.nextCalled);
+
+    assert.deepEqual(store.audit.map(e => [e.actor, e.action, e.outcome, e.target]), [
+        ['lector', 'pool.hide', 'denied', '3'],
+        [ALICE, 'pool.hide', 'ok', '3']
+    ]);
+    assert.equal(store.audit[1].details.body.code, undefined);
+});
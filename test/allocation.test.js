@@ -0,0 +1,42 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { apportion, computeAllocation, allocationToCsv } = require('../lib/allocation');
+
+const A = 'GA'.padEnd(56, 'A');
+const B = 'GB'.padEnd(56, 'B');
+const C = 'GC'.padEnd(56, 'C');
+
+test('apportion reparte exacto con resto determinista', () => {
+    // 10 unidades entre tres aportes iguales: el sobrante va por dirección
+    const r = apportion([{ key: C, weight: 1n }, { key: A, weight: 1n }, { key: B, weight: 1n }], 10n);
+    assert.deepEqual(r.map(x => [x.key, x.units]), [[C, 3n], [A, 4n], [B, 3n]]);
+
+    // Montos i128 grandes no pierden precisión
+    const big = 170141183460469231731687303715884105727n; // i128::MAX
+    const r2 = apportion([{ key: A, weight: big }, { key: B, weight: 1n }], big);
+    assert.equal(r2[0].units + r2[1].units, big);
+});
+
+test('allocation con metadatos de cantidad y CSV', () => {
+    const totals = new Map([[A, 600n], [B, 400n], [C, 0n]]);
+    const meta = { unit: 'm3', targetQuantity: 12.5, pricePerUnitClp: '105600' }; // 1 320 000 CLP de leña
+    const a = computeAllocation(totals, meta);
+
+    assert.equal(a.totalContributed, '1000');
+    assert.equal(a.totalValueClp, '1320000');
+    assert.deepEqual(a.members.map(m => [m.sharePpm, m.quantity, m.valueClp]), [
+        ['600000', '7.5', '792000'],
+        ['400000', '5', '528000']
+    ]);
+
+    const csv = allocationToCsv(a).trim().split('\n');
+    assert.equal(csv[0], 'address,contributed_stroops,share_ppm,quantity,unit,value_clp');
+    assert.equal(csv[1], `${A},600,600000,7.5,m3,792000`);
+});
+
+test('sin metadatos solo se reparte la proporción', () => {
+    const a = computeAllocation(new Map([[A, 1n], [B, 2n]]));
+    assert.equal(a.members[0].quantity, null);
+    assert.deepEqual(a.members.map(m => m.sharePpm), ['333333', '666667']);
+});
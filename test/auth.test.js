@@ -0,0 +1,56 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const crypto = require('crypto');
+const { Keypair } = require('@stellar/stellar-sdk');
+
+const { AuthError, createAuth } = require('../lib/auth');
+
+function memorySessions() {
+    const rows = new Map();
+    return {
+        saveSession: (hash, address, expiresAt) => rows.set(hash, { address, expiresAt }),
+        getSessionAddress: (hash, now) => {
+            const r = rows.get(hash);
+            return r && r.expiresAt > now ? r.address : null;
+        },
+        deleteSession: hash => rows.delete(hash)
+    };
+}
+
+// Igual que Freighter: ed25519 sobre sha256("Stellar Signed Message:\n" + mensaje), en base64
+function freighterSign(kp, message) {
+    const digest = crypto.createHash('sha256')
+        .update(Buffer.concat([Buffer.from('Stellar Signed Message:\n'), Buffer.from(message)]))
+        .digest();
+    return kp.sign(digest).toString('base64');
+}
+
+test('challenge firmado entrega una sesión ligada a la dirección', () => {
+    const auth = createAuth({ store: memorySessions() });
+    const kp = Keypair.random();
+    const ch = auth.challenge(kp.publicKey());
+
+    const session = auth.verify({ address: kp.publicKey(), nonce: ch.nonce, signature: freighterSign(kp, ch.message) });
+    assert.equal(auth.authenticate(session.token), kp.publicKey());
+
+    auth.logout(session.token);
+    assert.equal(auth.authenticate(session.token), null);
+});
+
+test('rechaza firma ajena, nonce reutilizado y dirección inválida', () => {
+    const auth = createAuth({ store: memorySessions() });
+    const kp = Keypair.random();
+    const other = Keypair.random();
+
+    const ch = auth.challenge(kp.publicKey());
+    assert.throws(
+        () => auth.verify({ address: kp.publicKey(), nonce: ch.nonce, signature: freighterSign(other, ch.message) }),
+        AuthError
+    );
+    // El nonce se consumió en el intento fallido
+    assert.throws(
+        () => auth.verify({ address: kp.publicKey(), nonce: ch.nonce, signature: freighterSign(kp, ch.message) }),
+        /unknown or expired nonce/
+    );
+    assert.throws(() => auth.challenge('not-an-address'), /invalid address/);
+});
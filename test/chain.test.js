@@ -0,0 +1,38 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { diffPool, createCacheChainReader, createTokenRegistry } = require('../lib/chain');
+
+const ALICE = 'GAIH3ULLFQ4DGSECF2AR555KZ4KNDGEKN4AFI4SU2M7B43MGK3QJZNSR';
+
+const onchain = {
+    id: 1, creator: ALICE, supplier: ALICE, token: 'CTOKEN',
+    goal: '1000000000', raised: '600000000', deadline: 1_700_000_000, finalized: false
+};
+
+test('diffPool reporta solo los campos on-chain que no cuadran', () => {
+    const submitted = { ...onchain, name: 'Leña', goal: '5', raised: 600000000, deadline: '1700000000', finalized: true };
+    assert.deepEqual(diffPool(submitted, onchain), {
+        goal: { submitted: '5', onchain: '1000000000' },
+        finalized: { submitted: true, onchain: false }
+    });
+    // Campos ausentes no cuentan como diferencia
+    assert.deepEqual(diffPool({ id: 1, name: 'x' }, onchain), {});
+});
+
+test('lector de cache devuelve null para pools sin pc', async () => {
+    const pools = new Map([['1', onchain], ['2', { id: 2, raised: '5' }]]);
+    const reader = createCacheChainReader(pools);
+    assert.equal((await reader.getPool(1)).goal, '1000000000');
+    assert.equal(await reader.getPool(2), null);
+    assert.equal(await reader.getPool(3), null);
+});
+
+test('registro de tokens lista decimales y símbolo sin repetir', async () => {
+    const reader = { getToken: async id => (id === 'CUSDC' ? { decimals: 6, symbol: 'USDC' } : null) };
+    const tokens = createTokenRegistry({ reader, nativeTokenId: 'CXLM' });
+    assert.deepEqual(await tokens.list(['CXLM', 'CUSDC', 'CXLM', null]), [
+        { id: 'CXLM', decimals: 7, symbol: 'XLM' },
+        { id: 'CUSDC', decimals: 6, symbol: 'USDC' }
+    ]);
+});
@@ -0,0 +1,69 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+// better-sqlite3 es dependencia del servidor: si falta (sin `npm ci`) estas pruebas fallan
+const Database = require('better-sqlite3');
+const { openStore } = require('../lib/db');
+
+const OTHER = 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC';
+const tmpFile = (name) => path.join(fs.mkdtempSync(path.join(os.tmpdir(), 'db-')), name);
+
+test('pool_id INTEGER de bases anteriores pasa a TEXT con la clave de pool', () => {
+    const file = tmpFile('old.db');
+    const old = new Database(file);
+    old.exec(`
+        CREATE TABLE contributions (id INTEGER PRIMARY KEY AUTOINCREMENT, pool_id INTEGER NOT NULL, contributor TEXT NOT NULL,
+            amount TEXT NOT NULL, ledger INTEGER, tx_hash TEXT, timestamp TEXT);
+        CREATE INDEX idx_contributions_pool ON contributions(pool_id);
+        CREATE TABLE refunds (id INTEGER PRIMARY KEY AUTOINCREMENT, pool_id INTEGER NOT NULL, address TEXT NOT NULL,
+            amount TEXT NOT NULL, ledger INTEGER, tx_hash TEXT, timestamp TEXT, event_id TEXT UNIQUE);
+        INSERT INTO contributions (pool_id, contributor, amount) VALUES ('3', 'GA', '100'), ('${OTHER}:3', 'GA', '50');
+        INSERT INTO refunds (pool_id, address, amount, event_id) VALUES ('3', 'GA', '40', 'e1');
+    `);
+    old.close();
+
+    const store = openStore(file);
+    const rows = store.contributionsByAccount('GA');
+    assert.deepEqual(rows.map(c => c.poolId).sort(), ['3', `${OTHER}:3`]);
+    assert.deepEqual(store.memberBalance(3, 'GA'), { contributed: 100n, refunded: 40n });
+
+    assert.equal(store.addContribution({ poolId: 3, contributor: 'GA', amount: '7', eventId: 'e2' }), true);
+    assert.deepEqual(store.contributionsByPool('3').map(c => [c.poolId, c.amount]), [['3', '100'], ['3', '7']]);
+
+    const check = new Database(file, { readonly: true });
+    for (const t of ['contributions', 'refunds', 'vouchers']) {
+        assert.equal(check.prepare(`PRAGMA table_info(${t})`).all().find(c => c.name === 'pool_id').type, 'TEXT');
+    }
+    assert.ok(check.prepare("SELECT 1 FROM sqlite_master WHERE name = 'idx_contributions_pool'").get());
+    check.close();
+});
+
+test('migrateFromJson importa una sola vez y se salta al reiniciar', () => {
+    const file = tmpFile('store.db');
+    const dir = path.dirname(file);
+    const stateFile = path.join(dir, 'state.json');
+    const txLogFile = path.join(dir, 'tx-log.json');
+    fs.writeFileSync(stateFile, JSON.stringify({
+        pools: [{ id: 1, name: 'papas', creator: 'GA', goal: '100', raised: '0', deadline: 10 }, { id: 2, name: 'miel' }, null],
+        hidden: [2],
+        lastScannedLedger: 500
+    }));
+    fs.writeFileSync(txLogFile, JSON.stringify([{ operation: 'CREATE_POOL', status: 'success', details: { id: 1 } }]));
+
+    let store = openStore(file);
+    assert.deepEqual(store.migrateFromJson(stateFile, txLogFile), { pools: 2, tx: 1 });
+    assert.deepEqual(store.loadPools().map(p => p.name).sort(), ['miel', 'papas']);
+    assert.deepEqual(store.loadHidden().map(String), ['2']);
+    assert.equal(store.getMeta('lastScannedLedger'), '500');
+    store.close();
+
+    // Reinicio: los JSON siguen ahí pero no se vuelven a importar
+    store = openStore(file);
+    assert.deepEqual(store.migrateFromJson(stateFile, txLogFile), { pools: 0, tx: 0 });
+    assert.equal(store.loadPools().length, 2);
+    assert.equal(store.listTx().length, 1);
+    store.close();
+});
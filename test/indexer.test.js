@@ -0,0 +1,275 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const path = require('path');
+
+const { nativeToScVal, xdr } = require('@stellar/stellar-sdk');
+
+const { createFixtureEventSource } = require('../lib/event-source');
+const { createIndexer, poolStatus, isActionable, refundableFor } = require('../lib/indexer');
+
+const FIXTURES = path.join(__dirname, 'fixtures', 'events.json');
+const CONTRACT_ID = 'CBAID77FC57C6LNDGPS2RTTWA6RZY72LXJYQMLZMX3NBO4VSWGXLTVT2';
+const ALICE = 'GAIH3ULLFQ4DGSECF2AR555KZ4KNDGEKN4AFI4SU2M7B43MGK3QJZNSR';
+const BOB = 'GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H';
+
+function newIndexer(store = null) {
+    const source = createFixtureEventSource(FIXTURES);
+    return createIndexer({ source, store, contractId: CONTRACT_ID });
+}
+
+// Store mínimo en memoria con la interfaz que usa el indexador
+function memoryStore() {
+    const meta = new Map();
+    const events = new Set();
+    return {
+        contributions: [],
+        refunds: [],
+        snapshots: 0,
+        saveSnapshot(_pools, m, ids) {
+            this.snapshots++;
+            for (const [k, v] of Object.entries(m)) meta.set(k, v == null ? null : String(v));
+            ids.forEach(id => events.add(id));
+        },
+        hasEvent: id => events.has(id),
+        addContribution(c) { this.contributions.push(c); return true; },
+        addRefund(r) { this.refunds.push(r); return true; },
+        loadPools: () => [],
+        getMeta: k => meta.get(k) ?? null
+    };
+}
+
+test('pc, ctr, rf y fn reconstruyen las pools', async () => {
+    const idx = newIndexer();
+    await idx.hydrate(0);
+
+    const p1 = idx.pools.get('1');
+    assert.equal(p1.goal, '1000000000');
+    assert.equal(p1.raised, '1000000000');
+    assert.equal(p1.finalized, true);
+
+    // 50 huérfanos aplicados al llegar pc, +30 de Alice, -50 reembolsados a Bob
+    const p2 = idx.pools.get('2');
+    assert.equal(p2.raised, '30000000');
+    assert.equal(p2.finalized, false);
+    assert.equal(idx.pendingRaised.size, 0);
+    assert.equal(idx.state.lastScannedLedger, 1100);
+});
+
+test('contribución huérfana queda en pendingRaised hasta ver pc', async () => {
+    const idx = newIndexer();
+    const source = createFixtureEventSource(FIXTURES);
+    const { events } = await source.getEvents({ startLedger: 1000, limit: 3 });
+    events.forEach(e => idx.applyEvent(e));
+
+    assert.equal(idx.pools.has('2'), false);
+    assert.equal(idx.pendingRaised.get('2'), 50000000n);
+});
+
+test('replay del mismo rango es un no-op', async () => {
+    const idx = newIndexer();
+    await idx.hydrate(0);
+    const before = JSON.stringify([...idx.pools.values()]);
+
+    await idx.hydrate(0);
+    await idx.hydrate(1000);
+    assert.equal(JSON.stringify([...idx.pools.values()]), before);
+});
+
+test('reanuda desde el cursor persistido sin repetir eventos', async () => {
+    const store = memoryStore();
+    const first = newIndexer(store);
+    await first.hydrate(0);
+    assert.equal(store.contributions.length, 4);
+    assert.deepEqual(store.refunds.map(r => r.address), [BOB]);
+    assert.equal(store.contributions[0].contributor, ALICE);
+
+    // Proceso nuevo, mismo store: el cursor apunta al último evento
+    const second = newIndexer(store);
+    second.state.eventCursor = store.getMeta('eventCursor');
+    await second.hydrate();
+    assert.equal(store.contributions.length, 4);
+    assert.equal(second.pools.size, 0);
+});
+
+test('releer desde el ledger 0 con el mismo store no duplica aportes ni reembolsos', async () => {
+    const store = memoryStore();
+    await newIndexer(store).hydrate(0);
+
+    // Sin cursor: los eventos ya aplicados se reconocen por id en el store
+    const again = newIndexer(store);
+    await again.hydrate(0);
+    assert.equal(store.contributions.length, 4);
+    assert.equal(store.refunds.length, 1);
+    assert.equal(again.pools.size, 0);
+    assert.equal(again.state.lastScannedLedger, 1100);
+});
+
+test('varios contratos: pools por (contrato, id) y eventos ajenos ignorados', async () => {
+    const OTHER_ID = 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC';
+    const store = memoryStore();
+    const idx = createIndexer({ source: null, store, contractIds: [CONTRACT_ID, OTHER_ID] });
+    const { events } = await createFixtureEventSource(FIXTURES).getEvents({ startLedger: 0, limit: 200 });
+
+    events.forEach(e => idx.applyEvent(e));
+    const n = store.contributions.length;
+    events.forEach(e => idx.applyEvent({ ...e, contractId: OTHER_ID }));
+    assert.equal(idx.applyEvent({ ...events[0], contractId: 'CUNKNOWN' }), false);
+
+    assert.equal(idx.pools.get('1').contract, CONTRACT_ID);
+    const other = idx.pools.get(`${OTHER_ID}:1`);
+    assert.equal(other.contract, OTHER_ID);
+    assert.equal(other.key, `${OTHER_ID}:1`);
+    assert.equal(other.raised, idx.pools.get('1').raised);
+    assert.deepEqual(store.contributions.slice(n).map(c => c.poolId)[0], `${OTHER_ID}:1`);
+
+    assert.deepEqual(idx.keys.parse(`${OTHER_ID}:2`), { contract: OTHER_ID, id: 2, key: `${OTHER_ID}:2` });
+    assert.equal(idx.keys.parse(`${CONTRACT_ID}:2`).key, '2');
+    assert.equal(idx.keys.parse('CUNKNOWN:2'), null);
+});
+
+// Evento armado a mano: tópicos [tag, id, extra?] y valor ya en ScVal
+function event(tag, id, value, extra = null) {
+    const topics = [nativeToScVal(tag, { type: 'symbol' }), nativeToScVal(id, { type: 'u32' })];
+    if (extra) topics.push(extra);
+    return { contractId: CONTRACT_ID, topics, value };
+}
+
+test('hitos: ms libera tramos y el reembolso de un hito vencido no descuenta raised', () => {
+    const store = memoryStore();
+    const idx = createIndexer({ source: null, store, contractId: CONTRACT_ID });
+    const milestone = (percent_bps, deadline) => nativeToScVal({ percent_bps, deadline }, {
+        type: { percent_bps: ['symbol', 'u32'], deadline: ['symbol', 'u64'] }
+    });
+    const pool = nativeToScVal({
+        id: 7, creator: ALICE, supplier: BOB, token: CONTRACT_ID,
+        goal: 1000n, raised: 0n, deadline: 2_000n, finalized: false,
+        milestones: xdr.ScVal.scvVec([milestone(4_000, 3_000), milestone(6_000, 4_000)]),
+        next_milestone: 0, released: 0n, refunded: 0n
+    }, {
+        type: {
+            id: ['symbol', 'u32'], creator: ['symbol', 'address'], supplier: ['symbol', 'address'],
+            token: ['symbol', 'address'], goal: ['symbol', 'i128'], raised: ['symbol', 'i128'],
+            deadline: ['symbol', 'u64'], next_milestone: ['symbol', 'u32'],
+            released: ['symbol', 'i128'], refunded: ['symbol', 'i128']
+        }
+    });
+    const i128 = n => nativeToScVal(n, { type: 'i128' });
+    const who = nativeToScVal(BOB, { type: 'address' });
+
+    idx.applyEvent(event('pc', 7, pool));
+    idx.applyEvent(event('ctr', 7, i128(1000n), who));
+    const change = idx.applyEvent(event('ms', 7, i128(400n), nativeToScVal(0, { type: 'u32' })));
+    assert.deepEqual([change.type, change.milestone, change.amount], ['ms', 0, '400']);
+
+    const p = idx.pools.get('7');
+    assert.deepEqual(p.milestones, [{ percent_bps: 4_000, deadline: 3_000 }, { percent_bps: 6_000, deadline: 4_000 }]);
+    assert.equal(p.next_milestone, 1);
+    assert.equal(p.members, 1);
+    assert.deepEqual(p.limits, { min_contribution: '0', max_contribution_per_member: '0', max_members: 0 });
+    assert.equal(p.released, '400');
+    assert.equal(poolStatus(p, 3_500), 'delivering');
+    assert.equal(poolStatus(p, 4_001), 'lapsed');
+    assert.equal(isActionable(p, 4_001), true);
+
+    idx.applyEvent(event('rf', 7, i128(600n), who));
+    assert.equal(p.raised, '1000');
+    assert.equal(p.refunded, '600');
+    assert.doesNotThrow(() => JSON.stringify(p));
+});
+
+test('registros: rg, ma y mr mantienen los miembros sin tocar las pools', () => {
+    const idx = createIndexer({ source: null, contractId: CONTRACT_ID });
+    const addr = a => nativeToScVal(a, { type: 'address' });
+    const count = n => nativeToScVal(n, { type: 'u32' });
+
+    const created = idx.applyEvent(event('rg', 2, addr(ALICE)));
+    assert.deepEqual([created.type, created.registryKey, created.registry.admin], ['rg', '2', ALICE]);
+    assert.equal(created.key, undefined);
+
+    idx.applyEvent(event('ma', 2, count(1), addr(ALICE)));
+    const added = idx.applyEvent(event('ma', 2, count(2), addr(BOB)));
+    assert.deepEqual([added.member, added.registry.members], [BOB, 2]);
+    idx.applyEvent(event('mr', 2, count(1), addr(ALICE)));
+
+    assert.deepEqual([...idx.registries.get('2').members], [BOB]);
+    assert.equal(idx.pools.size, 0);
+});
+
+test('xp, xv y xt: la extensión aprobada mueve el plazo de la pool', () => {
+    const idx = createIndexer({ source: null, contractId: CONTRACT_ID });
+    const u64 = n => nativeToScVal(n, { type: 'u64' });
+    const i128 = n => nativeToScVal(n, { type: 'i128' });
+    idx.pools.set('5', { id: 5, contract: CONTRACT_ID, key: '5', goal: '1000', raised: '900', deadline: 2_000, finalized: false });
+
+    assert.equal(idx.applyEvent(event('xp', 5, u64(2_600n))).deadline, 2_600);
+    const vote = idx.applyEvent(event('xv', 5, i128(600n), nativeToScVal(ALICE, { type: 'address' })));
+    assert.deepEqual([vote.type, vote.address, vote.amount], ['xv', ALICE, '600']);
+    assert.deepEqual(idx.pools.get('5').extension, { deadline: 2_600, votes: '600' });
+    assert.equal(poolStatus(idx.pools.get('5'), 2_100), 'expired');
+
+    const applied = idx.applyEvent(event('xt', 5, u64(2_600n)));
+    assert.equal(applied.pool.deadline, 2_600);
+    assert.equal(applied.pool.extension, null);
+    assert.equal(poolStatus(idx.pools.get('5'), 2_100), 'active');
+});
+
+test('refundableFor: hito vencido paga a prorrata de lo no liberado', () => {
+    const pool = {
+        goal: '1000', raised: '1000', deadline: 2_000, finalized: false, released: '400',
+        milestones: [{ percent_bps: 4_000, deadline: 3_000 }, { percent_bps: 6_000, deadline: 4_000 }], next_milestone: 1
+    };
+    const alice = { contributed: 750n, refunded: 0n };
+    assert.deepEqual(refundableFor(pool, alice, 3_500), { refundable: 0n, reason: 'goal_reached' });
+    assert.deepEqual(refundableFor(pool, alice, 4_001), { refundable: 450n, reason: null });
+    assert.deepEqual(refundableFor(pool, { contributed: 750n, refunded: 450n }, 4_001), { refundable: 0n, reason: 'already_refunded' });
+
+    const plain = { goal: '1000', raised: '300', deadline: 2_000, finalized: false };
+    assert.deepEqual(refundableFor(plain, { contributed: 300n, refunded: 0n }, 2_001), { refundable: 300n, reason: null });
+    assert.deepEqual(refundableFor({ ...plain, raised: '1000' }, { contributed: 300n, refunded: 0n }, 2_001), { refundable: 0n, reason: 'goal_reached' });
+});
+
+test('refundableFor: pool cancelada es reembolsable antes del vencimiento', () => {
+    const pool = { goal: '1000', raised: '300', deadline: 2_000, finalized: false, cancelled: true };
+    assert.deepEqual(refundableFor(pool, { contributed: 300n, refunded: 0n }, 1_000), { refundable: 300n, reason: null });
+    assert.deepEqual(refundableFor({ ...pool, cancelled: false }, { contributed: 300n, refunded: 0n }, 1_000), { refundable: 0n, reason: 'not_expired' });
+
+    // Con hitos ya financiados y una entrega liberada: la parte de lo no liberado
+    const delivering = {
+        ...pool, raised: '1000', released: '400', next_milestone: 1,
+        milestones: [{ percent_bps: 4_000, deadline: 3_000 }, { percent_bps: 6_000, deadline: 4_000 }]
+    };
+    assert.deepEqual(refundableFor(delivering, { contributed: 500n, refunded: 0n }, 2_500), { refundable: 300n, reason: null });
+    assert.deepEqual(refundableFor({ ...pool, finalized: true }, { contributed: 300n, refunded: 0n }, 1_000), { refundable: 0n, reason: 'finalized' });
+});
+
+test('poolStatus e isActionable', () => {
+    const now = 1_000;
+    const base = { goal: '100', raised: '10', deadline: 2_000, finalized: false };
+    assert.equal(poolStatus(base, now), 'active');
+    assert.equal(poolStatus({ ...base, raised: '100' }, now), 'funded');
+    assert.equal(poolStatus({ ...base, deadline: 500 }, now), 'expired');
+    assert.equal(poolStatus({ ...base, finalized: true }, now), 'finalized');
+    assert.equal(poolStatus({ ...base, raised: '100', cancelled: true }, now), 'cancelled');
+
+    assert.equal(isActionable(base, now), true);
+    assert.equal(isActionable({ ...base, deadline: 500 }, now), true);               // reembolsable
+    assert.equal(isActionable({ ...base, raised: '100', deadline: 500 }, now), true); // falta finalizar
+    assert.equal(isActionable({ ...base, finalized: true }, now), false);
+    assert.equal(isActionable({ ...base, cancelled: true }, now), true);                // reembolso inmediato
+    assert.equal(isActionable({ ...base, raised: '0', cancelled: true }, now), false); // ya devuelto todo
+});
+
+test('cn marca la pool cancelada y los reembolsos descuentan raised', () => {
+    const idx = createIndexer({ source: null, contractId: CONTRACT_ID });
+    const i128 = n => nativeToScVal(n, { type: 'i128' });
+    const who = nativeToScVal(BOB, { type: 'address' });
+    idx.pools.set('4', { id: 4, contract: CONTRACT_ID, key: '4', goal: '1000', raised: '300', deadline: 5_000, finalized: false });
+
+    const change = idx.applyEvent(event('cn', 4, i128(300n)));
+    assert.deepEqual([change.type, change.amount, change.pool.cancelled], ['cn', '300', true]);
+    assert.equal(poolStatus(idx.pools.get('4'), 1_000), 'cancelled');
+
+    idx.applyEvent(event('rf', 4, i128(300n), who));
+    assert.equal(idx.pools.get('4').raised, '0');
+    assert.equal(isActionable(idx.pools.get('4'), 1_000), false);
+});
@@ -0,0 +1,40 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const path = require('path');
+const { Asset, Networks } = require('@stellar/stellar-sdk');
+
+const { loadNetworkProfile } = require('../lib/network');
+
+const FILE = path.join(__dirname, '..', 'config', 'networks.json');
+
+test('perfil por defecto y token nativo derivado del passphrase', () => {
+    const p = loadNetworkProfile({ file: FILE, env: {} });
+    assert.equal(p.network, 'testnet');
+    assert.equal(p.networkPassphrase, Networks.TESTNET);
+    assert.equal(p.tokenId, Asset.native().contractId(Networks.TESTNET));
+});
+
+test('local exige contrato y acepta overrides por entorno', () => {
+    assert.throws(() => loadNetworkProfile({ file: FILE, name: 'local', env: {} }), /CONTRACT_ID/);
+    assert.throws(() => loadNetworkProfile({ file: FILE, name: 'nope', env: {} }), /red desconocida/);
+
+    const p = loadNetworkProfile({ file: FILE, name: 'local', env: { CONTRACT_ID: 'CLOCAL' } });
+    assert.equal(p.contractId, 'CLOCAL');
+    assert.equal(p.rpcUrl, 'http://localhost:8000/soroban/rpc');
+    assert.equal(p.tokenId, Asset.native().contractId('Standalone Network ; February 2017'));
+    assert.ok(p.friendbotUrl);
+});
+
+test('poolTerms: apagado en el contrato previo, encendido con un CONTRACT_ID redesplegado', () => {
+    assert.equal(loadNetworkProfile({ file: FILE, env: {} }).poolTerms, false);
+    assert.equal(loadNetworkProfile({ file: FILE, env: { CONTRACT_ID: 'CNEW' } }).poolTerms, true);
+    assert.equal(loadNetworkProfile({ file: FILE, env: { POOL_TERMS: '1' } }).poolTerms, true);
+    assert.equal(loadNetworkProfile({ file: FILE, env: { CONTRACT_ID: 'CNEW', POOL_TERMS: '0' } }).poolTerms, false);
+});
+
+test('tokens del perfil: CODIGO:EMISOR se resuelve al SAC de esa red', () => {
+    const p = loadNetworkProfile({ file: FILE, env: {} });
+    const usdc = new Asset('USDC', 'GBBD47IF6LWK7P7MDEVSCWR7DPUWV3NY3DTQEVFL4NAT4AQH3ZLLFLA5').contractId(Networks.TESTNET);
+    assert.deepEqual(p.tokenIds, [p.tokenId, usdc]);
+    assert.deepEqual(loadNetworkProfile({ file: FILE, env: { TOKEN_IDS: 'native,CUSDC' } }).tokenIds, [p.tokenId, 'CUSDC']);
+});
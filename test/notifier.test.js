@@ -0,0 +1,100 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { dueKinds, formatUnits, normalizeSubscription, createNotifier } = require('../lib/notifier');
+
+const ALICE = 'GAIH3ULLFQ4DGSECF2AR555KZ4KNDGEKN4AFI4SU2M7B43MGK3QJZNSR';
+const BOB = 'GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H';
+const NOW = 1_700_000_000;
+
+// Store en memoria con la interfaz de suscripciones/avisos de lib/db.js
+function memoryStore(totals, subs) {
+    const log = new Map();
+    const k = (p, kind, a, ch) => `${p}|${kind}|${a}|${ch}`;
+    return {
+        memberTotals: () => new Map(Object.entries(totals)),
+        memberBalance: (_p, a) => ({ contributed: totals[a] ?? 0n, refunded: 0n }),
+        getSubscription: a => subs[a] || null,
+        getNotification: (p, kind, a, ch) => log.get(k(p, kind, a, ch)) || null,
+        recordNotification(n) {
+            const prev = log.get(k(n.poolId, n.kind, n.address, n.channel));
+            log.set(k(n.poolId, n.kind, n.address, n.channel), { status: n.status, attempts: (prev?.attempts || 0) + 1 });
+        },
+        log
+    };
+}
+
+function recorder(name, { fail = false, accepts = () => true } = {}) {
+    const sent = [];
+    return { name, sent, accepts, async send(sub, msg) { if (fail) throw new Error('down'); sent.push([sub.address, msg.kind]); } };
+}
+
+test('qué aviso toca según meta, plazo y reloj', () => {
+    const base = { goal: '100', raised: '10', deadline: NOW + 3600, finalized: false };
+    assert.deepEqual(dueKinds(base, NOW, 24 * 3600), ['deadline_soon']);
+    assert.deepEqual(dueKinds({ ...base, deadline: NOW + 3 * 86400 }, NOW, 24 * 3600), []);
+    assert.deepEqual(dueKinds({ ...base, raised: '100' }, NOW, 24 * 3600), ['goal_reached']);
+    assert.deepEqual(dueKinds({ ...base, deadline: NOW - 1 }, NOW, 24 * 3600), ['refund_available']);
+    assert.deepEqual(dueKinds({ ...base, raised: '100', cancelled: true }, NOW, 24 * 3600), ['refund_available']);
+    assert.deepEqual(dueKinds({ ...base, raised: '100', finalized: true }, NOW, 24 * 3600), []);
+    assert.equal(formatUnits('125000000', 7), '12.5');
+    assert.throws(() => normalizeSubscription({ kinds: ['nope'] }), /kinds/);
+});
+
+test('avisa una sola vez por canal y reintenta los fallidos', async () => {
+    const pools = new Map([['1', { id: 1, key: '1', creator: ALICE, goal: '100', raised: '40', deadline: NOW - 60, finalized: false }]]);
+    const store = memoryStore({ [ALICE]: 0n, [BOB]: 40n }, {
+        [ALICE]: { address: ALICE, email: 'a@coop.cl' },
+        [BOB]: { address: BOB, email: 'b@coop.cl' }
+    });
+    const file = recorder('file');
+    const hook = recorder('webhook', { fail: true });
+    const notifier = createNotifier({ pools, store, channels: [file, hook], maxAttempts: 2 });
+
+    assert.equal(await notifier.tick(NOW), 1);
+    // Reembolso solo a quien tiene aporte neto; Alice (creadora, sin aporte) no
+    assert.deepEqual(file.sent, [[BOB, 'refund_available']]);
+
+    await notifier.tick(NOW);
+    await notifier.tick(NOW);
+    assert.equal(file.sent.length, 1);
+    assert.equal(store.log.get(`1|refund_available|${BOB}|webhook`).attempts, 2);
+});
+
+test('hito vencido mucho después del plazo de la pool igual avisa el reembolso', async () => {
+    const DAY = 86400;
+    const pool = {
+        id: 1, key: '1', creator: ALICE, goal: '100', raised: '100', deadline: NOW - 30 * DAY, finalized: false,
+        next_milestone: 1,
+        milestones: [{ percent_bps: 5000, deadline: NOW - 20 * DAY }, { percent_bps: 5000, deadline: NOW - 3600 }]
+    };
+    const store = memoryStore({ [BOB]: 100n }, { [BOB]: { address: BOB, email: 'b@coop.cl' } });
+    const file = recorder('file');
+    const notifier = createNotifier({ pools: new Map([['1', pool]]), store, channels: [file] });
+
+    assert.equal(await notifier.tick(NOW), 1);
+    assert.deepEqual(file.sent, [[BOB, 'refund_available']]);
+
+    // El lookback corre desde el hito vencido: 8 días después ya no se avisa
+    const late = recorder('file');
+    const old = createNotifier({ pools: new Map([['1', pool]]), store: memoryStore({ [BOB]: 100n }, { [BOB]: { address: BOB } }), channels: [late] });
+    assert.equal(await old.tick(NOW + 8 * DAY), 0);
+});
+
+test('cancelada mucho después del plazo avisa con lo reembolsable a prorrata', async () => {
+    const DAY = 86400;
+    const pool = {
+        id: 1, key: '1', creator: ALICE, goal: '1000000000', raised: '1000000000', released: '400000000',
+        deadline: NOW - 30 * DAY, finalized: false, cancelled: true, cancelled_at: NOW - 3600, next_milestone: 1,
+        milestones: [{ percent_bps: 4000, deadline: NOW - 20 * DAY }, { percent_bps: 6000, deadline: NOW + 20 * DAY }]
+    };
+    const store = memoryStore({ [BOB]: 1000000000n }, { [BOB]: { address: BOB } });
+    const msgs = [];
+    const channel = { name: 'file', accepts: () => true, async send(_sub, msg) { msgs.push(msg); } };
+    const notifier = createNotifier({ pools: new Map([['1', pool]]), store, channels: [channel] });
+
+    assert.equal(await notifier.tick(NOW), 1);
+    assert.equal(msgs[0].kind, 'refund_available');
+    assert.equal(msgs[0].refundable, '600000000');
+    assert.match(msgs[0].text, /cancelada.*reembolso de 60 XLM \(tu parte de lo no liberado\)/);
+});
@@ -0,0 +1,53 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+const { createHttpPriceSource, createFilePriceSource, createPriceOracle } = require('../lib/price');
+
+function metaStore() {
+    const meta = new Map();
+    return { getMeta: k => meta.get(k) ?? null, setMeta: (k, v) => meta.set(k, v == null ? null : String(v)) };
+}
+
+test('feed HTTP con cache, stale y fallback', async () => {
+    let calls = 0;
+    let fail = false;
+    const source = createHttpPriceSource({
+        fetchImpl: async () => {
+            calls++;
+            if (fail) throw new Error('offline');
+            return { ok: true, json: async () => ({ stellar: { clp: 312.4, last_updated_at: 1_700_000_000 } }) };
+        }
+    });
+
+    const down = createPriceOracle({ source: { kind: 'http', fetch: async () => { throw new Error('x'); } }, fallbackRate: 100 });
+    assert.deepEqual(await down.get(), { pair: 'XLM/CLP', rate: 100, source: 'fallback', updatedAt: null, stale: true });
+
+    const oracle = createPriceOracle({ source, ttlMs: -1 }); // siempre vencido
+    const first = await oracle.get();
+    assert.equal(first.rate, 312.4);
+    assert.equal(first.updatedAt, '2023-11-14T22:13:20.000Z');
+
+    fail = true;
+    const stale = await oracle.get();
+    assert.equal(stale.rate, 312.4);
+    assert.equal(stale.stale, true);
+    assert.equal(calls, 2);
+});
+
+test('archivo local y override manual', async () => {
+    const file = path.join(fs.mkdtempSync(path.join(os.tmpdir(), 'price-')), 'xlm-clp.json');
+    fs.writeFileSync(file, JSON.stringify({ rate: 250, updatedAt: '2026-01-01T00:00:00.000Z' }));
+
+    const oracle = createPriceOracle({ source: createFilePriceSource(file), store: metaStore() });
+    assert.equal((await oracle.get()).rate, 250);
+
+    const o = await oracle.setOverride('275.5', 'admin');
+    assert.equal(o.source, 'override');
+    assert.equal(o.rate, 275.5);
+    await assert.rejects(oracle.setOverride(-1), /positive/);
+
+    assert.equal((await oracle.clearOverride()).source, 'file');
+});
@@ -0,0 +1,52 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const path = require('path');
+
+const { createFixtureEventSource } = require('../lib/event-source');
+const { createIndexer } = require('../lib/indexer');
+const { createPoolStream } = require('../lib/stream');
+
+const FIXTURES = path.join(__dirname, 'fixtures', 'events.json');
+const CONTRACT_ID = 'CBAID77FC57C6LNDGPS2RTTWA6RZY72LXJYQMLZMX3NBO4VSWGXLTVT2';
+
+function fakeClient() {
+    return { chunks: [], write(c) { this.chunks.push(c); }, end() {} };
+}
+
+test('el stream emite un mensaje por evento nuevo y los cambios de estado', async () => {
+    let stream;
+    const idx = createIndexer({
+        source: createFixtureEventSource(FIXTURES),
+        contractId: CONTRACT_ID,
+        onEvent: (c) => stream.onPoolEvent(c)
+    });
+    stream = createPoolStream({ pools: idx.pools });
+    const client = fakeClient();
+    stream.clients.add(client);
+
+    await idx.hydrate(0);
+    const pool = client.chunks.filter(c => c.includes('event: pool\n'));
+    assert.equal(pool.length, 8);
+    assert.match(pool[5], /"type":"fn","poolId":1/);
+
+    // Los deadlines de los fixtures ya pasaron: pool 1 va de expired a finalized
+    const status = client.chunks.filter(c => c.includes('event: status\n')).map(c => JSON.parse(c.split('data: ')[1]));
+    assert.deepEqual(status.filter(s => s.poolId === 1).map(s => [s.from, s.to]), [['expired', 'finalized']]);
+
+    // Replay: nada nuevo que emitir
+    const before = client.chunks.length;
+    await idx.hydrate(0);
+    assert.equal(client.chunks.length, before);
+});
+
+test('un vencimiento por reloj se emite como status', () => {
+    const pools = new Map([['7', { id: 7, goal: '100', raised: '10', deadline: 1_000, finalized: false }]]);
+    const stream = createPoolStream({ pools });
+    const client = fakeClient();
+    stream.clients.add(client);
+
+    stream.checkStatuses(900);
+    stream.checkStatuses(1_001);
+    assert.equal(client.chunks.length, 1);
+    assert.match(client.chunks[0], /"from":"active","to":"expired"/);
+});
@@ -0,0 +1,100 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const StellarSdk = require('@stellar/stellar-sdk');
+
+const { TxBuildError, contractErrorName, createTxBuilder } = require('../lib/tx-builder');
+
+const CONTRACT_ID = 'CBAID77FC57C6LNDGPS2RTTWA6RZY72LXJYQMLZMX3NBO4VSWGXLTVT2';
+const OTHER_ID = 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC';
+
+// RPC inalcanzable: solo se prueba lo que se valida antes de tocar la red
+const builder = createTxBuilder({
+    rpcUrl: 'http://127.0.0.1:9', contractId: CONTRACT_ID, networkPassphrase: StellarSdk.Networks.TESTNET
+});
+
+function signedCall(contract, fn, args = [StellarSdk.nativeToScVal(1, { type: 'u32' })]) {
+    const kp = StellarSdk.Keypair.random();
+    const tx = new StellarSdk.TransactionBuilder(new StellarSdk.Account(kp.publicKey(), '1'), {
+        fee: StellarSdk.BASE_FEE, networkPassphrase: StellarSdk.Networks.TESTNET
+    })
+        .addOperation(StellarSdk.Operation.invokeContractFunction({
+            contract, function: fn, args
+        }))
+        .setTimeout(30)
+        .build();
+    tx.sign(kp);
+    return tx.toXDR();
+}
+
+test('build valida acción y parámetros antes de simular', async () => {
+    await assert.rejects(builder.build('withdraw', {}), e => e instanceof TxBuildError && e.status === 404);
+    await assert.rejects(builder.build('refund', { poolId: 1, user: 'nope' }), /user must be a G/);
+    await assert.rejects(builder.build('contribute', { poolId: 1, from: StellarSdk.Keypair.random().publicKey(), amount: '-5' }),
+        /amount must be a positive integer/);
+    await assert.rejects(builder.build('create_pool', {
+        creator: StellarSdk.Keypair.random().publicKey(), token: CONTRACT_ID, supplier: StellarSdk.Keypair.random().publicKey(),
+        goal: '100', deadline: '2000000000', milestones: [{ percent_bps: 10000, deadline: 'soon' }]
+    }), /milestones\[0\]\.deadline must be a positive integer/);
+    await assert.rejects(builder.build('create_pool', {
+        creator: StellarSdk.Keypair.random().publicKey(), token: CONTRACT_ID, supplier: StellarSdk.Keypair.random().publicKey(),
+        goal: '100', deadline: '2000000000', limits: { min_contribution: '-1' }
+    }), /limits\.min_contribution must be a non-negative integer/);
+    await assert.rejects(builder.build('release_milestone', { poolId: 1, creator: 'nope' }), /creator must be a G/);
+    await assert.rejects(builder.build('create_pool', {
+        creator: StellarSdk.Keypair.random().publicKey(), token: CONTRACT_ID, supplier: StellarSdk.Keypair.random().publicKey(),
+        goal: '100', deadline: '2000000000', registry: 'club'
+    }), /registry must be a positive integer/);
+    await assert.rejects(builder.build('create_pool', {
+        creator: StellarSdk.Keypair.random().publicKey(), token: CONTRACT_ID, supplier: StellarSdk.Keypair.random().publicKey(),
+        goal: '100', deadline: '2000000000', extension_quorum_bps: 12_000
+    }), /extension_quorum_bps must be at most 10000/);
+    await assert.rejects(builder.build('propose_extension', {
+        poolId: 1, proposer: StellarSdk.Keypair.random().publicKey(), deadline: 'later'
+    }), /deadline must be a positive integer/);
+    await assert.rejects(builder.build('add_member', {
+        registryId: 1, admin: StellarSdk.Keypair.random().publicKey(), member: CONTRACT_ID
+    }), /member must be a G/);
+});
+
+test('sin poolTerms, create_pool rechaza terms que el contrato desplegado no entiende', async () => {
+    const legacy = createTxBuilder({
+        rpcUrl: 'http://127.0.0.1:9', contractId: CONTRACT_ID, networkPassphrase: StellarSdk.Networks.TESTNET, poolTerms: false
+    });
+    const base = {
+        creator: StellarSdk.Keypair.random().publicKey(), token: CONTRACT_ID, supplier: StellarSdk.Keypair.random().publicKey(),
+        goal: '100', deadline: '2000000000'
+    };
+    for (const terms of [
+        { milestones: [{ percent_bps: 10000, deadline: '2000000000' }] },
+        { limits: { max_members: 5 } },
+        { registry: 1 },
+        { extension_quorum_bps: 5000 }
+    ]) {
+        await assert.rejects(legacy.build('create_pool', { ...base, ...terms }), e => e.status === 409 && /redeploy/.test(e.message));
+    }
+    // sin terms se llega a la simulación (RPC inalcanzable), no al rechazo
+    await assert.rejects(legacy.build('create_pool', { ...base, limits: { min_contribution: '0' } }), e => !/redeploy/.test(e.message));
+});
+
+test('submit solo retransmite invocaciones al contrato de pools', async () => {
+    await assert.rejects(builder.submit('not-xdr'), /invalid transaction XDR/);
+    await assert.rejects(builder.submit(signedCall(OTHER_ID, 'transfer')), /does not target the pool contract/);
+    // approve solo sobre el token de una pool indexada y a favor del contrato de pools
+    await assert.rejects(builder.submit(signedCall(OTHER_ID, 'approve')), /does not target the pool contract/);
+    const withToken = createTxBuilder({
+        rpcUrl: 'http://127.0.0.1:9', contractId: CONTRACT_ID, networkPassphrase: StellarSdk.Networks.TESTNET,
+        poolTokens: () => [OTHER_ID]
+    });
+    const owner = StellarSdk.nativeToScVal(StellarSdk.Keypair.random().publicKey(), { type: 'address' });
+    const thief = StellarSdk.nativeToScVal(StellarSdk.Keypair.random().publicKey(), { type: 'address' });
+    await assert.rejects(withToken.submit(signedCall(OTHER_ID, 'approve', [owner, thief])), /spender must be the pool contract/);
+    // Contratos fuera de la lista indexada no se aceptan ni para armar
+    await assert.rejects(builder.build('finalize', { poolId: 1, contract: OTHER_ID, creator: StellarSdk.Keypair.random().publicKey() }),
+        /contract is not indexed/);
+    assert.equal(contractErrorName('HostError: Error(Contract, #12)'), 'GoalExceeded');
+    assert.equal(contractErrorName('HostError: Error(Contract, #16)'), 'MilestoneLapsed');
+    assert.equal(contractErrorName('HostError: Error(Contract, #20)'), 'MaxMembersReached');
+    assert.equal(contractErrorName('HostError: Error(Contract, #23)'), 'NotMember');
+    assert.equal(contractErrorName('HostError: Error(Contract, #25)'), 'PoolCancelled');
+    assert.equal(contractErrorName('HostError: Error(Contract, #29)'), 'AlreadyVoted');
+});
@@ -0,0 +1,67 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { createTxTracker } = require('../lib/tx-tracker');
+
+const HASH = 'ab'.repeat(32);
+
+function memoryStore() {
+    const rows = new Map();
+    return {
+        log: [],
+        getTracked: h => rows.get(h) || null,
+        pendingTracked: () => [...rows.values()].filter(r => r.status === 'PENDING'),
+        saveTracked(r) { rows.set(r.hash, { ...r }); },
+        appendTx(e) { this.log.push(e); }
+    };
+}
+
+test('PENDING -> SUCCESS queda en el tx log y dispara el callback', async () => {
+    const store = memoryStore();
+    const responses = [{ status: 'NOT_FOUND' }, { status: 'SUCCESS', ledger: 1234 }];
+    const calls = [];
+    const tracker = createTxTracker({
+        rpcUrl: 'http://rpc.test',
+        store,
+        callbackUrl: 'http://callback.test/tx',
+        fetchImpl: async (url, opts) => {
+            calls.push([url, JSON.parse(opts.body)]);
+            return { ok: true, status: 200, json: async () => ({ jsonrpc: '2.0', id: 1, result: responses.shift() }) };
+        }
+    });
+
+    tracker.track(HASH.toUpperCase(), { fn: 'refund' });
+    tracker.track(HASH); // idempotente
+    assert.equal(tracker.pendingCount(), 1);
+    await tracker.tick();
+    assert.equal(tracker.get(HASH).status, 'PENDING');
+
+    await tracker.tick();
+    const row = tracker.get(HASH);
+    assert.equal(row.status, 'SUCCESS');
+    assert.equal(row.ledger, 1234);
+    assert.equal(row.callback_status, 'sent');
+    assert.equal(tracker.pendingCount(), 0);
+    assert.deepEqual(store.log.map(e => [e.details.from, e.details.to]), [[null, 'PENDING'], ['PENDING', 'SUCCESS']]);
+    const rpc = calls.filter(c => c[0] === 'http://rpc.test');
+    assert.equal(rpc.length, 2);
+    assert.deepEqual(rpc[0][1].params, { hash: HASH });
+    assert.equal(rpc[0][1].method, 'getTransaction');
+    const cb = calls.filter(c => c[0] === 'http://callback.test/tx');
+    assert.equal(cb.length, 1);
+    assert.equal(cb[0][1].status, 'SUCCESS');
+});
+
+test('sin respuesta tras maxAgeMs pasa a NOT_FOUND', async () => {
+    const store = memoryStore();
+    const tracker = createTxTracker({
+        rpcUrl: 'http://rpc.test',
+        store,
+        maxAgeMs: -1,
+        fetchImpl: async () => ({ ok: true, status: 200, json: async () => ({ result: { status: 'NOT_FOUND' } }) })
+    });
+    tracker.track(HASH);
+    await tracker.tick();
+    assert.equal(tracker.get(HASH).status, 'NOT_FOUND');
+    assert.throws(() => tracker.track('xyz'), /invalid transaction hash/);
+});
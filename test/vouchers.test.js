@@ -0,0 +1,80 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+
+const { QR_PREFIX, VoucherError, createVoucherService } = require('../lib/vouchers');
+
+const ALICE = 'GAIH3ULLFQ4DGSECF2AR555KZ4KNDGEKN4AFI4SU2M7B43MGK3QJZNSR';
+const BOB = 'GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H';
+const SUPPLIER = 'GDSUPPLIERXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX';
+
+// Store en memoria con la interfaz de vouchers + memberBalance de lib/db.js
+function memoryStore(balances) {
+    const rows = new Map();
+    // pool_id con afinidad INTEGER: "1" queda 1, "C...:1" queda texto
+    const norm = p => (/^\d+$/.test(String(p)) ? Number(p) : String(p));
+    const byMember = (p, a) => [...rows.values()].filter(v => v.poolId === norm(p) && v.contributor === a);
+    return {
+        memberBalance: (p, a) => balances[a] || { contributed: 0n, refunded: 0n },
+        vouchersByMember: byMember,
+        issueVoucher(v, capacity) {
+            const issued = byMember(v.poolId, v.contributor).reduce((acc, r) => acc + BigInt(r.amount), 0n);
+            if (issued + BigInt(v.amount) > BigInt(capacity)) return null;
+            const row = { ...v, poolId: norm(v.poolId), status: 'issued', issuedAt: 'now' };
+            rows.set(v.code, row);
+            return { ...row };
+        },
+        getVoucher: code => (rows.has(code) ? { ...rows.get(code) } : null),
+        redeemVoucher(code, by) {
+            const r = rows.get(code);
+            if (!r || r.status !== 'issued') return false;
+            Object.assign(r, { status: 'redeemed', redeemedBy: by });
+            return true;
+        }
+    };
+}
+
+const pool = { id: 1, supplier: SUPPLIER, finalized: true };
+
+function service() {
+    return createVoucherService({
+        store: memoryStore({ [ALICE]: { contributed: 600n, refunded: 0n }, [BOB]: { contributed: 50n, refunded: 50n } }),
+        secret: 'test-secret'
+    });
+}
+
+test('el tope es el aporte neto del miembro', () => {
+    const svc = service();
+    const v = svc.issue(pool, ALICE, '400');
+    assert.match(v.code, /^AGRO-[0-9A-Z]{4}-[0-9A-Z]{4}-[0-9A-Z]{4}$/);
+    assert.ok(v.qrPayload.startsWith(`${QR_PREFIX}:${v.code}:1:400:`));
+
+    assert.throws(() => svc.issue(pool, ALICE, '201'), e => e instanceof VoucherError && e.details.available === '200');
+    assert.equal(svc.issue(pool, ALICE, '200').amount, '200');
+    assert.throws(() => svc.issue(pool, BOB, '1'), /no_contribution/);
+    assert.throws(() => svc.issue({ ...pool, finalized: false }, ALICE, '1'), /not_finalized/);
+});
+
+test('verificación por código o QR y canje de un solo uso', () => {
+    const svc = service();
+    const v = svc.issue(pool, ALICE, '100');
+
+    assert.equal(svc.verify(v.code.toLowerCase()).valid, true);
+    assert.equal(svc.verify(v.qrPayload).valid, true);
+    assert.equal(svc.verify(v.qrPayload.replace(':100:', ':900:')).reason, 'bad_signature');
+    assert.equal(svc.verify('AGRO-0000-0000-0000').reason, 'not_found');
+
+    assert.throws(() => svc.redeem(v.code, ALICE), e => e.status === 403);
+    assert.equal(svc.redeem(v.qrPayload, SUPPLIER).status, 'redeemed');
+    assert.throws(() => svc.redeem(v.code, SUPPLIER), e => e.status === 409);
+    assert.equal(svc.verify(v.code).reason, 'redeemed');
+});
+
+test('QR de una pool de otro contrato (clave con ":")', () => {
+    const svc = service();
+    const key = 'CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC:1';
+    const v = svc.issue({ ...pool, key }, ALICE, '100');
+
+    assert.equal(v.poolId, key);
+    assert.equal(svc.verify(v.qrPayload).valid, true);
+    assert.equal(svc.verify(v.qrPayload.replace(':1:100:', ':2:100:')).reason, 'bad_signature');
+});